@@ -2,6 +2,13 @@ use std::fs::{self, File};
 use std::io::Write;
 use std::path::Path;
 
+// Tests que necesitan mas de un hart
+const MULTI_HART_TESTS: [(&str, usize); 2] = [("rv32mi-p-fence_i_smp", 2), ("rv32mi-p-smp", 2)];
+// Tests que esperan que los accesos desalineados provoquen una excepcion
+const MISALIGNED_TRAP_TESTS: [&str; 2] = ["rv32mi-p-ma_trap", "rv32ud-p-d_extra"];
+// Tests que activan una interrupcion del PLIC escribiendo en IrqTrigger
+const IRQ_TRIGGER_TESTS: [&str; 1] = ["rv32mi-p-plic"];
+
 fn main() {
     let out_path = Path::new("src/tests.rs");
 
@@ -11,57 +18,99 @@ fn main() {
     writeln!(
         file,
         r#"
-use crate::interpreter::Interpreter;
+use crate::interpreter::{{ExitStatus, Interpreter, MisalignedAccess}};
+use crate::interpreter::device::{{BusError, Device}};
 use ntest::timeout;
+
+// Dispositivo que activa su linea de interrupcion cada vez que se escribe en el
+#[derive(Default)]
+struct IrqTrigger {{
+    raised: bool,
+}}
+
+impl Device for IrqTrigger {{
+    fn read_u8(&mut self, _offset: u64) -> Result<u8, BusError> {{
+        Ok(0)
+    }}
+
+    fn write_u8(&mut self, _offset: u64, _val: u8) -> Result<(), BusError> {{
+        self.raised = true;
+        Ok(())
+    }}
+
+    fn take_interrupt(&mut self) -> bool {{
+        std::mem::take(&mut self.raised)
+    }}
+}}
 "#
     )
     .unwrap();
 
-    let paths = fs::read_dir("rv_tests").expect("No se pudo leer el directorio tests");
+    let mut paths: Vec<_> = fs::read_dir("rv_tests")
+        .expect("No se pudo leer el directorio tests")
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
 
-    for entry in paths {
-        let path = entry.unwrap().path();
-        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-            if name.ends_with(".hex") {
-                let test_name = name
-                    .trim_end_matches(".hex")
-                    .replace("-", "_")
-                    .replace(".", "_");
+    for path in paths {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
 
-                let mut to_host: u32 = 0x80001000;
+        // Los .hex se cargan directamente en memoria y usan el tohost por defecto
+        // de riscv-tests. Los .elf se cargan segun sus segmentos y el simbolo
+        // tohost indica donde esta el HTIF.
+        let (stem, load) = if let Some(stem) = name.strip_suffix(".hex") {
+            (stem, "load_hex")
+        } else if let Some(stem) = name.strip_suffix(".elf") {
+            (stem, "load_elf")
+        } else {
+            continue;
+        };
 
-                if test_name == "rv32ui_p_ld_st" {
-                    to_host = 0x80002000;
-                }
+        let num_harts = MULTI_HART_TESTS
+            .iter()
+            .find(|(test, _)| *test == stem)
+            .map_or(1, |(_, num_harts)| *num_harts);
 
-                writeln!(
-                    file,
-                    r#"
-#[test]
-#[timeout(2000)]
-fn {test_name}() {{
-    use std::panic;
+        let (constructor, mut setup) = match (load, num_harts) {
+            ("load_hex", 1) => ("Interpreter::with_htif(0x80001000, 0x80001040)".to_string(), String::new()),
+            ("load_hex", _) => (
+                format!("Interpreter::with_harts({num_harts})"),
+                "\n    interpreter.set_htif(0x80001000, 0x80001040);".to_string(),
+            ),
+            (_, 1) => ("Interpreter::default()".to_string(), String::new()),
+            _ => (format!("Interpreter::with_harts({num_harts})"), String::new()),
+        };
 
-    let result = panic::catch_unwind(|| {{
-        let mut interpreter = Interpreter::new_test({to_host:#08X});
-        interpreter.load_hex("rv_tests/{name}");
-        interpreter.run();
-    }});
+        if MISALIGNED_TRAP_TESTS.contains(&stem) {
+            setup.push_str("\n    interpreter.set_misaligned_access(MisalignedAccess::Trap);");
+        }
 
-    let err = result.expect_err("Expected panic");
+        if IRQ_TRIGGER_TESTS.contains(&stem) {
+            setup.push_str(
+                "\n    interpreter.add_device(0x10001000, 0x1000, Some(11), Box::new(IrqTrigger::default())).unwrap();",
+            );
+        }
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+        let test_name = stem
+            .replace("-", "_")
+            .replace(".", "_");
 
-    assert!(msg.contains("PASS"));
+        writeln!(
+            file,
+            r#"
+#[test]
+#[timeout(2000)]
+fn {test_name}() {{
+    let mut interpreter = {constructor};{setup}
+    interpreter.{load}("rv_tests/{name}").unwrap();
+
+    assert_eq!(interpreter.run(), ExitStatus::Halted {{ exit_code: 0 }});
 }}
 "#
-                )
-                .unwrap();
-            }
-        }
+        )
+        .unwrap();
     }
 
     // // Cierre del módulo tests
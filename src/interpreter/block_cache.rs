@@ -0,0 +1,141 @@
+// Cache de bloques basicos ya decodificados. Cada hart tiene la suya, como una
+// cache de instrucciones.
+//
+// Un bloque es una secuencia de instrucciones de la DRAM que se ejecutan una
+// detras de otra. Termina en la primera instruccion que puede cambiar el flujo
+// de control, la traduccion de direcciones o el modo de privilegio (saltos,
+// instrucciones de sistema y fences), al final de la pagina o al llegar a
+// MAX_BLOCK_LEN instrucciones. Como no cruza de pagina basta con traducir la
+// direccion de su primera instruccion. Las regiones de PMP pueden acabar a
+// mitad de un bloque, asi que cada instruccion se comprueba al ejecutarla.
+//
+// Los bloques se indexan por la direccion fisica de su primera instruccion. El
+// bus lleva la cuenta de las paginas que tienen bloques y avisa cuando se
+// escribe en ellas. Las escrituras de un hart solo descartan sus propios
+// bloques: los demas harts siguen ejecutando las instrucciones antiguas hasta
+// que ejecutan fence.i, que vacia la cache del hart. Las escrituras desde fuera
+// de la ejecucion (cargar un programa, GDB) descartan los bloques de todos.
+
+use std::{collections::HashMap, rc::Rc};
+
+use crate::interpreter::{
+    bus::Bus,
+    extensions::rv32c::{expand_compressed, is_compressed},
+    riscv_core::{InstructionType, RVCore},
+    virtual_memory::sv32::{PAGESIZE, PhysicalAddress},
+};
+
+const MAX_BLOCK_LEN: usize = 64;
+
+const OPCODE_MISC_MEM: u32 = 0b0001111;
+const OPCODE_BRANCH: u32 = 0b1100011;
+const OPCODE_JALR: u32 = 0b1100111;
+const OPCODE_JAL: u32 = 0b1101111;
+const OPCODE_SYSTEM: u32 = 0b1110011;
+
+pub struct DecodedInstr {
+    pub instr: InstructionType,
+    // Instruccion tal y como esta en memoria, para la traza
+    pub raw: u32,
+    pub len: u32,
+}
+
+pub struct Block {
+    pub instrs: Vec<DecodedInstr>,
+}
+
+// Siguiente instruccion del bloque que esta ejecutando el hart y sus
+// direcciones virtual y fisica. Si coincide con el pc se ejecuta sin traducirlo
+// ni buscar el bloque
+pub struct BlockCursor {
+    pub block: Rc<Block>,
+    pub index: usize,
+    pub pc: u32,
+    pub phys_address: u64,
+}
+
+#[derive(Default)]
+pub struct BlockCache {
+    // Bloques de cada pagina fisica indexados por su direccion
+    pages: HashMap<u64, HashMap<u64, Rc<Block>>>,
+}
+
+impl BlockCache {
+    pub fn get(&self, phys_address: u64) -> Option<Rc<Block>> {
+        self.pages.get(&page_of(phys_address))?.get(&phys_address).cloned()
+    }
+
+    pub fn insert(&mut self, phys_address: u64, block: Rc<Block>) {
+        self.pages.entry(page_of(phys_address)).or_default().insert(phys_address, block);
+    }
+
+    pub fn invalidate_page(&mut self, page: u64) {
+        self.pages.remove(&page);
+    }
+
+    pub fn clear(&mut self) {
+        self.pages.clear();
+    }
+}
+
+pub fn page_of(phys_address: u64) -> u64 {
+    phys_address / PAGESIZE as u64
+}
+
+// Decodifica el bloque que empieza en phys_address. Devuelve None si no esta en
+// la DRAM o si la primera instruccion no se puede decodificar o cruza de
+// pagina, y entonces la instruccion se decodifica sin guardarla
+pub fn decode_block(core: &mut RVCore, bus: &mut Bus, phys_address: u64) -> Option<Block> {
+    if !bus.is_dram(phys_address) {
+        return None;
+    }
+
+    let mut instrs = Vec::new();
+    let mut address = phys_address;
+
+    while instrs.len() < MAX_BLOCK_LEN && page_of(address) == page_of(phys_address) {
+        let Some((raw, len)) = read_instr(bus, address) else {
+            break;
+        };
+
+        let Some(instr) = core.decode(raw) else {
+            break;
+        };
+
+        instrs.push(DecodedInstr { instr, raw, len });
+        address += len as u64;
+
+        if ends_block(raw) {
+            break;
+        }
+    }
+
+    (!instrs.is_empty()).then_some(Block { instrs })
+}
+
+// Las instrucciones de 32 bits que cruzan de pagina no se guardan
+fn read_instr(bus: &mut Bus, address: u64) -> Option<(u32, u32)> {
+    let low = bus.read_halfword(&PhysicalAddress(address)).ok()? as u32;
+
+    if is_compressed(low) {
+        return Some((low, 2));
+    }
+
+    if page_of(address + 2) != page_of(address) {
+        return None;
+    }
+
+    let high = bus.read_halfword(&PhysicalAddress(address + 2)).ok()? as u32;
+
+    Some(((high << 16) | low, 4))
+}
+
+fn ends_block(raw: u32) -> bool {
+    let instr = if is_compressed(raw) {
+        expand_compressed(raw as u16).unwrap_or(0)
+    } else {
+        raw
+    };
+
+    matches!(instr & 0x7F, OPCODE_MISC_MEM | OPCODE_BRANCH | OPCODE_JALR | OPCODE_JAL | OPCODE_SYSTEM)
+}
@@ -1,164 +1,490 @@
-#![allow(clippy::items_after_test_module)]
-
-use std::{collections::HashSet, io::{Write, stdout}};
-
-use crate::{
-    interpreter::{NUM_HARTS, riscv_core::{Exception, ExceptionType}, virtual_memory::sv32::{PhysicalAddress, translate_address}},
-    peripherals::uart_16550::Uart16550,
-};
-
-pub const DRAM_BASE: usize = 0x80000000;
-pub const DRAM_SIZE: usize = 8 * 1024 * 1024;
-pub const DRAM_END: usize = DRAM_BASE + DRAM_SIZE;
-
-pub const ROM_BASE: usize = 0x00001000;
-pub const ROM_SIZE: usize = 0x00001000;
-pub const ROM_END: usize = ROM_BASE + ROM_SIZE;
-
-pub const UART_BASE: usize = 0x10000000;
-pub const UART_SIZE: usize = 0x100;
-pub const UART_END: usize = UART_BASE + UART_SIZE;
-
-pub struct Bus {
-    #[cfg(test)]
-    to_host: usize,
-
-    pub dram: Vec<u8>,
-
-    rom: Vec<u8>,
-    pub uart: Uart16550,
-
-    // PARA RV32A
-    reserved_addresses: [HashSet<usize>; NUM_HARTS],
-}
-
-impl Default for Bus {
-    fn default() -> Self {
-        Self {
-            #[cfg(test)]
-            to_host: 0x80001000,
-
-            dram: vec![0x00; DRAM_SIZE],
-            rom: vec![0x00; ROM_SIZE],
-            uart: Uart16550::new(),
-            reserved_addresses: [HashSet::new(); NUM_HARTS],
-        }
-    }
-}
-
-impl Bus {
-    #[cfg(test)]
-    pub fn new_test(to_host: usize) -> Self {
-        let mut bus = Self::default();
-        bus.to_host = to_host;
-
-        bus
-    }
-
-    pub fn read_byte(&self, phys_address: &PhysicalAddress) -> Result<u8, Exception> {
-        let address = phys_address.0 as usize;
-
-        match address {
-            DRAM_BASE..DRAM_END => Ok(self.dram[address - DRAM_BASE]),
-            ROM_BASE..ROM_END => Ok(self.rom[address - ROM_BASE]),
-            UART_BASE..UART_END => Ok(self.uart.read(address - UART_BASE)),
-            _ => Err(Exception::new(ExceptionType::LoadAccessFault, 0)),
-        }
-    }
-
-    pub fn write_byte(&mut self, phys_address: &PhysicalAddress, val: u8) -> Result<(), Exception> {
-        let address = phys_address.0 as usize;
-
-        match address {
-            DRAM_BASE..DRAM_END => {
-                self.dram[address - DRAM_BASE] = val;
-
-                for i in 0..NUM_HARTS {
-                    if self.is_address_reserved(i, address) {
-                        self.invalidate_reserved_address(i, address);
-                    }
-                }
-
-                Ok(())
-            }
-            UART_BASE..UART_END => {
-                self.uart.write(address - UART_BASE, val);
-                Ok(())
-            }
-
-            _ => Err(Exception::new(ExceptionType::StoreAmoAccessFault, 0)),
-        }
-    }
-
-    pub fn read_aligned_word(&self, phys_address: &PhysicalAddress) -> Result<u32, Exception> {
-        if phys_address.0 % 4 != 0 {
-            return Err(Exception::new(ExceptionType::LoadAddressMisaligned, 0));
-        }
-
-        #[cfg(test)]
-        if (self.to_host + 0x40) == phys_address.0 as usize {
-            return Ok(1);
-        }
-
-        #[cfg(not(test))]
-        if (0x80001000 + 0x40) == phys_address.0 as usize {
-            return Ok(1);
-        }
-
-        let val_0 = self.read_byte(phys_address)?;
-        let val_1 = self.read_byte(&phys_address.wrapping_add(1))?;
-        let val_2 = self.read_byte(&phys_address.wrapping_add(2))?;
-        let val_3 = self.read_byte(&phys_address.wrapping_add(3))?;
-
-        Ok(u32::from_le_bytes([val_0, val_1, val_2, val_3]))
-    }
-
-    pub fn read_word(&self, phys_address: &PhysicalAddress) -> Result<u32, Exception> {
-        let val_0 = self.read_byte(phys_address)?;
-        let val_1 = self.read_byte(&phys_address.wrapping_add(1))?;
-        let val_2 = self.read_byte(&phys_address.wrapping_add(2))?;
-        let val_3 = self.read_byte(&phys_address.wrapping_add(3))?;
-
-        Ok(u32::from_le_bytes([val_0, val_1, val_2, val_3]))
-    }
-
-    pub fn write_aligned_word(&mut self, phys_address: &PhysicalAddress, word: u32) -> Result<(), Exception> {
-        if phys_address.0 % 4 != 0 {
-            return Err(Exception::new(ExceptionType::StoreAmoAddressMisaligned, 0));
-        }
-
-        if phys_address.0 == 0x80001000 {
-            println!("{:#016X} {:#08X}", phys_address.0, word);
-
-        }
-
-        #[cfg(test)]
-        if phys_address.0 == self.to_host as u64 {
-            println!("{word}");
-            if word == 1 {
-                panic!("PASS");
-            } else {
-                panic!("FAIL {}", word);
-            }
-            
-        }
-
-        let bytes = word.to_le_bytes();
-        self.write_byte(phys_address, bytes[0])?;
-        self.write_byte(&phys_address.wrapping_add(1), bytes[1])?;
-        self.write_byte(&phys_address.wrapping_add(2), bytes[2])?;
-        self.write_byte(&phys_address.wrapping_add(3), bytes[3])
-    }
-
-    pub fn reserve_address(&mut self, hart_id: usize, address: usize) {
-        self.reserved_addresses[hart_id].insert(address);
-    }
-
-    pub fn invalidate_reserved_address(&mut self, hart_id: usize, address: usize) {
-        self.reserved_addresses[hart_id].remove(&address);
-    }
-
-    pub fn is_address_reserved(&self, hart_id: usize, address: usize) -> bool {
-        self.reserved_addresses[hart_id].contains(&address)
-    }
-}
+#![allow(clippy::items_after_test_module)]
+
+use std::fmt;
+
+use crate::{
+    interpreter::{block_cache::page_of, config::MachineConfig, device::Device, riscv_core::{Exception, ExceptionType}, snapshot::{SnapshotError, SnapshotReader, SnapshotWriter}, virtual_memory::{access::MisalignedAccess, sv32::PhysicalAddress}},
+    peripherals::{clint::Clint, htif::Htif, plic::Plic, rom::Rom, uart_16550::Uart16550},
+};
+
+// Tamaños de las regiones de los dispositivos. Sus direcciones y las de la
+// DRAM estan en MachineConfig
+pub const ROM_SIZE: usize = 0x00001000;
+pub const CLINT_SIZE: usize = 0x10000;
+pub const PLIC_SIZE: usize = 0x04000000;
+pub const UART_SIZE: usize = 0x100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegionKind {
+    Dram,
+    Rom,
+    Clint,
+    Plic,
+    Uart,
+    // Dispositivo registrado con Bus::add_device. Indice en Bus::devices
+    Device(usize),
+}
+
+#[derive(Clone, Copy)]
+pub struct Region {
+    pub kind: RegionKind,
+    pub base: u64,
+    pub size: u64,
+}
+
+impl Region {
+    fn contains(&self, address: u64, size: u64) -> bool {
+        address >= self.base && address - self.base + size <= self.size
+    }
+
+    fn overlaps(&self, other: &Region) -> bool {
+        self.base < other.base + other.size && other.base < self.base + self.size
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} [{:#010X}, {:#010X})", self.kind, self.base, self.base + self.size)
+    }
+}
+
+// Regiones del mapa de memoria segun la configuracion. La DRAM va primero
+// porque es la que mas se accede
+pub fn memory_map(config: &MachineConfig) -> Vec<Region> {
+    let region = |kind, base: u32, size: usize| Region {
+        kind,
+        base: base as u64,
+        size: size as u64,
+    };
+
+    vec![
+        region(RegionKind::Dram, config.dram_base, config.dram_size),
+        region(RegionKind::Rom, config.rom_base, ROM_SIZE),
+        region(RegionKind::Clint, config.clint_base, CLINT_SIZE),
+        region(RegionKind::Plic, config.plic_base, PLIC_SIZE),
+        region(RegionKind::Uart, config.uart_base, UART_SIZE),
+    ]
+}
+
+// Dispositivo registrado desde fuera del emulador y su linea del PLIC
+struct MappedDevice {
+    device: Box<dyn Device>,
+    irq: Option<u32>,
+}
+
+pub struct Bus {
+    regions: Vec<Region>,
+
+    pub dram: Vec<u8>,
+
+    rom: Rom,
+    pub clint: Clint,
+    pub plic: Plic,
+    pub uart: Uart16550,
+    uart_irq: u32,
+    devices: Vec<MappedDevice>,
+    // Solo existe si se ha configurado tohost o lo define el ELF cargado
+    pub htif: Option<Htif>,
+
+    // Bytes escritos por la instruccion en curso, solo con la traza activa
+    pub written_bytes: Option<Vec<(u64, u8)>>,
+
+    // Que hacer con las cargas y almacenamientos desalineados. Los accesos
+    // atomicos desalineados siempre provocan una excepcion
+    pub misaligned_access: MisalignedAccess,
+
+    // PARA RV32A. Cada hart tiene como mucho una reserva de una palabra
+    reserved_addresses: Vec<Option<usize>>,
+
+    // PARA LA CACHE DE BLOQUES. Paginas de la DRAM con instrucciones
+    // decodificadas y paginas en las que se ha escrito desde entonces
+    code_pages: Vec<bool>,
+    stale_code_pages: Vec<u64>,
+}
+
+impl Default for Bus {
+    fn default() -> Self {
+        Self::new(&MachineConfig::default())
+    }
+}
+
+impl Bus {
+    pub fn new(config: &MachineConfig) -> Self {
+        if let Err(err) = config.validate() {
+            panic!("Configuracion de la maquina no valida: {err}");
+        }
+
+        let dram_end = config.dram_base as u64 + config.dram_size as u64;
+        let dram_pages = (page_of(dram_end - 1) - page_of(config.dram_base as u64) + 1) as usize;
+
+        Self {
+            regions: memory_map(config),
+            dram: vec![0x00; config.dram_size],
+            rom: Rom::new(ROM_SIZE),
+            clint: Clint::new(config.num_harts),
+            plic: Plic::new(config.num_harts),
+            uart: Uart16550::new(),
+            uart_irq: config.uart_irq,
+            devices: Vec::new(),
+            htif: None,
+            written_bytes: None,
+            misaligned_access: MisalignedAccess::default(),
+            reserved_addresses: vec![None; config.num_harts],
+            code_pages: vec![false; dram_pages],
+            stale_code_pages: Vec::new(),
+        }
+    }
+
+    // Registra un dispositivo en [base, base + size). Si tiene irq sus
+    // interrupciones llegan al PLIC por esa linea
+    pub fn add_device(&mut self, base: u64, size: u64, irq: Option<u32>, device: Box<dyn Device>) -> Result<(), String> {
+        let region = Region {
+            kind: RegionKind::Device(self.devices.len()),
+            base,
+            size,
+        };
+
+        if size == 0 || base + size > 1 << 32 {
+            return Err(format!("{region} no cabe en el espacio de direcciones"));
+        }
+
+        if let Some(other) = self.regions.iter().find(|other| other.overlaps(&region)) {
+            return Err(format!("{region} se solapa con {other}"));
+        }
+
+        if let Some(irq) = irq
+            && !Plic::is_valid_source(irq)
+        {
+            return Err(format!("la linea {irq} del PLIC no existe"));
+        }
+
+        self.regions.push(region);
+        self.devices.push(MappedDevice { device, irq });
+
+        Ok(())
+    }
+
+    // Region que contiene el acceso entero y desplazamiento dentro de ella
+    fn find_region(&self, address: u64, size: u32) -> Option<(RegionKind, u64)> {
+        self.regions
+            .iter()
+            .find(|region| region.contains(address, size as u64))
+            .map(|region| (region.kind, address - region.base))
+    }
+
+    fn device_mut(&mut self, kind: RegionKind) -> &mut dyn Device {
+        match kind {
+            RegionKind::Rom => &mut self.rom,
+            RegionKind::Clint => &mut self.clint,
+            RegionKind::Plic => &mut self.plic,
+            RegionKind::Uart => &mut self.uart,
+            RegionKind::Device(index) => self.devices[index].device.as_mut(),
+            RegionKind::Dram => unreachable!("La DRAM no es un dispositivo"),
+        }
+    }
+
+    // Todos los dispositivos: los de la maquina y despues los registrados
+    fn devices_mut(&mut self) -> impl Iterator<Item = &mut (dyn Device + 'static)> {
+        let builtin: [&mut (dyn Device + 'static); 4] = [&mut self.rom, &mut self.clint, &mut self.plic, &mut self.uart];
+
+        builtin.into_iter().chain(self.devices.iter_mut().map(|mapped| mapped.device.as_mut()))
+    }
+
+    // tohost y fromhost suelen estar dentro de la DRAM
+    fn overlaps_htif(&self, address: u64, size: u32) -> bool {
+        self.htif
+            .as_ref()
+            .is_some_and(|htif| (0..size as u64).any(|byte| htif.contains((address + byte) as usize)))
+    }
+
+    // Todos los bytes del rango estan en alguna region o en el HTIF
+    pub fn is_mapped(&self, address: u64, size: u32) -> bool {
+        (0..size as u64).all(|byte| {
+            let address = address + byte;
+            self.find_region(address, 1).is_some() || self.overlaps_htif(address, 1)
+        })
+    }
+
+    // Lee size bytes (1, 2, 4 u 8) de una direccion alineada a size
+    pub fn read(&mut self, phys_address: &PhysicalAddress, size: u32) -> Result<u64, Exception> {
+        let address = phys_address.0;
+        let access_fault = Exception::new(ExceptionType::LoadAccessFault, 0);
+
+        if self.overlaps_htif(address, size) {
+            let mut bytes = [0u8; 8];
+
+            for (byte, val) in bytes[..size as usize].iter_mut().enumerate() {
+                let address = address + byte as u64;
+
+                *val = match &self.htif {
+                    Some(htif) if htif.contains(address as usize) => htif.read(address as usize),
+                    _ => self.read(&PhysicalAddress(address), 1)? as u8,
+                };
+            }
+
+            return Ok(u64::from_le_bytes(bytes));
+        }
+
+        let (kind, offset) = self.find_region(address, size).ok_or(access_fault)?;
+
+        if kind == RegionKind::Dram {
+            let offset = offset as usize;
+            let mut bytes = [0u8; 8];
+            bytes[..size as usize].copy_from_slice(&self.dram[offset..offset + size as usize]);
+
+            return Ok(u64::from_le_bytes(bytes));
+        }
+
+        let device = self.device_mut(kind);
+
+        match size {
+            1 => device.read_u8(offset).map(u64::from),
+            2 => device.read_u16(offset).map(u64::from),
+            4 => device.read_u32(offset).map(u64::from),
+            8 => device.read_u64(offset),
+            _ => unreachable!("Tamaño de acceso no valido: {size}"),
+        }
+        .map_err(|_| access_fault)
+    }
+
+    // Escribe los size bytes bajos de val (1, 2, 4 u 8) en una direccion
+    // alineada a size
+    pub fn write(&mut self, phys_address: &PhysicalAddress, size: u32, val: u64) -> Result<(), Exception> {
+        let address = phys_address.0;
+        let access_fault = Exception::new(ExceptionType::StoreAmoAccessFault, 0);
+        let bytes = val.to_le_bytes();
+
+        if let Some(written_bytes) = &mut self.written_bytes {
+            for (byte, val) in bytes[..size as usize].iter().enumerate() {
+                written_bytes.push((address + byte as u64, *val));
+            }
+        }
+
+        if self.overlaps_htif(address, size) {
+            let written_bytes = self.written_bytes.take();
+
+            for (byte, val) in bytes[..size as usize].iter().enumerate() {
+                let address = address + byte as u64;
+
+                match &mut self.htif {
+                    Some(htif) if htif.contains(address as usize) => htif.write(address as usize, *val),
+                    _ => self.write(&PhysicalAddress(address), 1, *val as u64)?,
+                }
+            }
+
+            self.written_bytes = written_bytes;
+            return Ok(());
+        }
+
+        let (kind, offset) = self.find_region(address, size).ok_or(access_fault)?;
+
+        if kind == RegionKind::Dram {
+            let offset = offset as usize;
+            self.dram[offset..offset + size as usize].copy_from_slice(&bytes[..size as usize]);
+            self.invalidate_code_page(address);
+
+            // Cualquier escritura en una palabra reservada rompe la reserva de
+            // todos los harts
+            for word in (address..address + size as u64).step_by(4) {
+                for hart_id in 0..self.reserved_addresses.len() {
+                    self.invalidate_reserved_address(hart_id, word as usize);
+                }
+            }
+
+            return Ok(());
+        }
+
+        let device = self.device_mut(kind);
+
+        match size {
+            1 => device.write_u8(offset, val as u8),
+            2 => device.write_u16(offset, val as u16),
+            4 => device.write_u32(offset, val as u32),
+            8 => device.write_u64(offset, val),
+            _ => unreachable!("Tamaño de acceso no valido: {size}"),
+        }
+        .map_err(|_| access_fault)
+    }
+
+    pub fn read_byte(&mut self, phys_address: &PhysicalAddress) -> Result<u8, Exception> {
+        Ok(self.read(phys_address, 1)? as u8)
+    }
+
+    pub fn write_byte(&mut self, phys_address: &PhysicalAddress, val: u8) -> Result<(), Exception> {
+        self.write(phys_address, 1, val as u64)
+    }
+
+    pub fn read_halfword(&mut self, phys_address: &PhysicalAddress) -> Result<u16, Exception> {
+        if !phys_address.0.is_multiple_of(2) {
+            return Err(Exception::new(ExceptionType::LoadAddressMisaligned, 0));
+        }
+
+        Ok(self.read(phys_address, 2)? as u16)
+    }
+
+    pub fn read_aligned_word(&mut self, phys_address: &PhysicalAddress) -> Result<u32, Exception> {
+        if !phys_address.0.is_multiple_of(4) {
+            return Err(Exception::new(ExceptionType::LoadAddressMisaligned, 0));
+        }
+
+        Ok(self.read(phys_address, 4)? as u32)
+    }
+
+    pub fn write_aligned_word(&mut self, phys_address: &PhysicalAddress, word: u32) -> Result<(), Exception> {
+        if !phys_address.0.is_multiple_of(4) {
+            return Err(Exception::new(ExceptionType::StoreAmoAddressMisaligned, 0));
+        }
+
+        self.write(phys_address, 4, word as u64)
+    }
+
+    // Se llama una vez por cada instruccion ejecutada
+    pub fn tick(&mut self) {
+        for device in self.devices_mut() {
+            device.tick();
+        }
+    }
+
+    // Pasa al PLIC las interrupciones que han activado los dispositivos
+    pub fn update_interrupts(&mut self) {
+        if self.uart.take_interrupt() {
+            self.plic.raise(self.uart_irq);
+        }
+
+        for mapped in &mut self.devices {
+            if mapped.device.take_interrupt()
+                && let Some(irq) = mapped.irq
+            {
+                self.plic.raise(irq);
+            }
+        }
+    }
+
+    // Devuelve los dispositivos al estado de reset y anula las reservas. El
+    // contenido de la DRAM y de la ROM se mantiene
+    pub fn reset(&mut self) {
+        for device in self.devices_mut() {
+            device.reset();
+        }
+
+        if let Some(htif) = &mut self.htif {
+            htif.reset();
+        }
+
+        self.reserved_addresses.fill(None);
+        self.clear_code_pages();
+    }
+
+    pub fn is_dram(&self, address: u64) -> bool {
+        self.regions[0].contains(address, 1)
+    }
+
+    fn code_page_index(&self, address: u64) -> usize {
+        (page_of(address) - page_of(self.regions[0].base)) as usize
+    }
+
+    // La pagina de la DRAM que contiene la direccion tiene bloques decodificados
+    pub fn mark_code_page(&mut self, address: u64) {
+        let index = self.code_page_index(address);
+        self.code_pages[index] = true;
+    }
+
+    fn invalidate_code_page(&mut self, address: u64) {
+        let index = self.code_page_index(address);
+
+        if self.code_pages[index] {
+            self.code_pages[index] = false;
+            self.stale_code_pages.push(page_of(address));
+        }
+    }
+
+    // Ningun hart tiene bloques decodificados
+    pub fn clear_code_pages(&mut self) {
+        self.code_pages.fill(false);
+        self.stale_code_pages.clear();
+    }
+
+    pub fn has_stale_code_pages(&self) -> bool {
+        !self.stale_code_pages.is_empty()
+    }
+
+    // Paginas cuyos bloques hay que descartar
+    pub fn take_stale_code_pages(&mut self) -> Vec<u64> {
+        std::mem::take(&mut self.stale_code_pages)
+    }
+
+    // Un lr.w sustituye la reserva anterior del hart
+    pub fn reserve_address(&mut self, hart_id: usize, address: usize) {
+        self.reserved_addresses[hart_id] = Some(address & !0b11);
+    }
+
+    pub fn invalidate_reserved_address(&mut self, hart_id: usize, address: usize) {
+        if self.is_address_reserved(hart_id, address) {
+            self.reserved_addresses[hart_id] = None;
+        }
+    }
+
+    pub fn is_address_reserved(&self, hart_id: usize, address: usize) -> bool {
+        self.reserved_addresses[hart_id] == Some(address & !0b11)
+    }
+
+    // La politica de accesos desalineados es configuracion del emulador y no se
+    // guarda. Tampoco se guarda el mapa de memoria
+    pub fn save_state(&self, writer: &mut SnapshotWriter) {
+        writer.write_u32(self.dram.len() as u32);
+        writer.write_bytes(&self.dram);
+        self.rom.save_state(writer);
+
+        for reservation in &self.reserved_addresses {
+            writer.write_bool(reservation.is_some());
+            writer.write_u32(reservation.unwrap_or(0) as u32);
+        }
+
+        self.clint.save_state(writer);
+        self.plic.save_state(writer);
+        self.uart.save_state(writer);
+
+        writer.write_bool(self.htif.is_some());
+        if let Some(htif) = &self.htif {
+            htif.save_state(writer);
+        }
+
+        writer.write_u32(self.devices.len() as u32);
+        for mapped in &self.devices {
+            mapped.device.save_state(writer);
+        }
+    }
+
+    pub fn load_state(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        reader.expect_u32("tamaño de la DRAM", self.dram.len() as u32)?;
+        let dram_size = self.dram.len();
+        self.dram.copy_from_slice(reader.read_bytes(dram_size)?);
+        self.clear_code_pages();
+        self.rom.load_state(reader)?;
+
+        for reservation in &mut self.reserved_addresses {
+            let reserved = reader.read_bool()?;
+            let address = reader.read_u32()? as usize;
+            *reservation = reserved.then_some(address);
+        }
+
+        self.clint.load_state(reader)?;
+        self.plic.load_state(reader)?;
+        self.uart.load_state(reader)?;
+
+        self.htif = if reader.read_bool()? {
+            Some(Htif::load_state(reader)?)
+        } else {
+            None
+        };
+
+        // Los dispositivos registrados tienen que ser los mismos y estar en el
+        // mismo orden
+        reader.expect_u32("numero de dispositivos", self.devices.len() as u32)?;
+        for mapped in &mut self.devices {
+            mapped.device.load_state(reader)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+// Configuracion de la maquina emulada: numero de harts, DRAM y direcciones de
+// los dispositivos. Los tamaños de las regiones de los dispositivos son fijos
+// (ver bus.rs).
+//
+// Por defecto se usa el mapa de memoria de la maquina virt de QEMU, con una
+// DRAM de 8 MiB.
+
+use crate::{interpreter::bus::memory_map, peripherals::plic::Plic};
+
+#[derive(Clone, Debug)]
+pub struct MachineConfig {
+    pub num_harts: usize,
+
+    pub dram_base: u32,
+    pub dram_size: usize,
+
+    pub rom_base: u32,
+    pub clint_base: u32,
+    pub plic_base: u32,
+    pub uart_base: u32,
+    // Linea de interrupcion de la UART en el PLIC
+    pub uart_irq: u32,
+}
+
+impl Default for MachineConfig {
+    fn default() -> Self {
+        Self {
+            num_harts: 1,
+            dram_base: 0x80000000,
+            dram_size: 8 * 1024 * 1024,
+            rom_base: 0x00001000,
+            clint_base: 0x02000000,
+            plic_base: 0x0C000000,
+            uart_base: 0x10000000,
+            uart_irq: 10,
+        }
+    }
+}
+
+impl MachineConfig {
+    // Las regiones tienen que caber en el espacio de direcciones de 32 bits y
+    // no pueden solaparse. La linea de la UART tiene que existir en el PLIC
+    pub fn validate(&self) -> Result<(), String> {
+        if self.num_harts == 0 {
+            return Err("tiene que haber al menos un hart".to_string());
+        }
+
+        if !Plic::is_valid_source(self.uart_irq) {
+            return Err(format!("la linea {} del PLIC no existe", self.uart_irq));
+        }
+
+        let mut regions = memory_map(self);
+        regions.sort_by_key(|region| region.base);
+
+        for region in &regions {
+            if region.size == 0 || region.base + region.size > 1 << 32 {
+                return Err(format!("{region} no cabe en el espacio de direcciones"));
+            }
+        }
+
+        for pair in regions.windows(2) {
+            if pair[0].base + pair[0].size > pair[1].base {
+                return Err(format!("{} se solapa con {}", pair[0], pair[1]));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MachineConfig;
+    use crate::interpreter::{ExitStatus, Interpreter, virtual_memory::sv32::PhysicalAddress};
+
+    fn validate(config: MachineConfig) -> String {
+        config.validate().unwrap_err()
+    }
+
+    #[test]
+    fn rejects_invalid_configs() {
+        let default = MachineConfig::default;
+
+        assert!(validate(MachineConfig { num_harts: 0, ..default() }).contains("hart"));
+        assert!(validate(MachineConfig { uart_irq: 0, ..default() }).contains("PLIC"));
+        assert!(validate(MachineConfig { dram_size: 0, ..default() }).contains("no cabe"));
+        assert!(validate(MachineConfig { dram_base: 0xFFC00000, ..default() }).contains("no cabe"));
+        assert!(validate(MachineConfig { uart_base: 0x0C100000, ..default() }).contains("se solapa"));
+        assert!(validate(MachineConfig { dram_base: 0x00000000, ..default() }).contains("se solapa"));
+    }
+
+    #[test]
+    fn runs_with_relocated_dram() {
+        let config = MachineConfig {
+            dram_base: 0x40000000,
+            ..MachineConfig::default()
+        };
+        assert!(config.validate().is_ok());
+
+        let program: [u32; 5] = [
+            0x00001317, // auipc t1, 0x1
+            0x00100293, // li t0, 1
+            0x00532023, // sw t0, 0(t1)
+            0x00032223, // sw zero, 4(t1)
+            0x0000006f, // j .
+        ];
+
+        let mut interpreter = Interpreter::with_config(config);
+        interpreter.set_htif(0x40001000, 0x40001040);
+
+        for (i, byte) in program.iter().flat_map(|instr| instr.to_le_bytes()).enumerate() {
+            interpreter.bus.write_byte(&PhysicalAddress(0x40000000 + i as u64), byte).unwrap();
+        }
+
+        assert_eq!(interpreter.get_pc(), 0x40000000);
+        assert_eq!(interpreter.run_for(100), ExitStatus::Halted { exit_code: 0 });
+    }
+}
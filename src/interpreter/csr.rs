@@ -1,4 +1,8 @@
-use crate::interpreter::riscv_core::{Exception, ExceptionType, PrivilegeLevel};
+use crate::interpreter::{
+    riscv_core::{Exception, ExceptionType, InterruptType, PrivilegeLevel},
+    snapshot::{SnapshotError, SnapshotReader, SnapshotWriter},
+    virtual_memory::pmp::{PMP_ENTRIES, PmpConfig, is_pmpaddr_locked},
+};
 use bitfield::bitfield;
 
 /*
@@ -19,10 +23,33 @@ pub const MIDELEG: usize = 0x303;
 const MIE: usize = 0x304;
 const MIE_MASK: u32 = 0xFFFF2AAA;
 pub const MTVEC: usize = 0x305;
+pub const TVEC_BASE_MASK: u32 = 0xFFFFFFFC;
+pub const TVEC_MODE_MASK: u32 = 0b11;
+pub const TVEC_MODE_VECTORED: u32 = 0b01;
 const MCOUNTEREN: usize = 0x306;
+const MENVCFG: usize = 0x30A;
+// Solo FIOM, que no tiene efecto porque todos los accesos estan ordenados
+const MENVCFG_MASK: u32 = 0x1;
+const MENVCFGH: usize = 0x31A;
+// ADUE (bit 61): el hardware actualiza A y D en las PTEs (Svadu)
+const MENVCFGH_ADUE: u32 = 1 << 29;
 pub const MSTATUSH: usize = 0x310;
 const MSTATUSH_MASK: u32 = 0x6F0;
 pub const MEDELEGH: usize = 0x312;
+const MCOUNTINHIBIT: usize = 0x320;
+// Todos menos TM, time no se puede detener
+const MCOUNTINHIBIT_MASK: u32 = 0xFFFFFFFD;
+const MHPMEVENT3: usize = 0x323;
+const MHPMEVENT31: usize = 0x33F;
+// Parte alta de mhpmevent (Sscofpmf)
+const MHPMEVENTH3: usize = 0x723;
+const MHPMEVENTH31: usize = 0x73F;
+// OF, MINH, SINH y UINH. El resto del selector de evento vale 0
+const MHPMEVENTH_MASK: u32 = 0xF0000000;
+const MHPMEVENTH_OF: u32 = 1 << 31;
+const MHPMEVENTH_MINH: u32 = 1 << 30;
+const MHPMEVENTH_SINH: u32 = 1 << 29;
+const MHPMEVENTH_UINH: u32 = 1 << 28;
 // TRAP HANDLING
 const MSCRATCH: usize = 0x340;
 pub const MEPC: usize = 0x341;
@@ -30,15 +57,50 @@ pub const MCAUSE: usize = 0x342;
 pub const MTVAL: usize = 0x343;
 const MIP: usize = 0x344;
 const MIP_MASK: u32 = 0xFFFF2AAA;
+// MSIP, MTIP, MEIP y SEIP los controlan los dispositivos (CLINT/PLIC)
+const MIP_WRITE_MASK: u32 = 0x00002022;
 const MTINST: usize = 0x34A;
 const MTVAL2: usize = 0x34B;
+// MEMORY PROTECTION
+const PMPCFG0: usize = 0x3A0;
+const PMPCFG3: usize = 0x3A3;
+const PMPCFG15: usize = 0x3AF;
+const PMPADDR0: usize = 0x3B0;
+const PMPADDR15: usize = PMPADDR0 + PMP_ENTRIES - 1;
+const PMPADDR63: usize = 0x3EF;
 // COUNTER/TIMERS
 const MCYCLE: usize = 0xB00;
 const MINSTRET: usize = 0xB02;
-const MHPCOUNTER3: usize = 0xB03; // MAX 31 (-3)
+const MHPCOUNTER3: usize = 0xB03;
+const MHPCOUNTER31: usize = 0xB1F;
 const MCYCLEH: usize = 0xB80;
 const MINSTRETH: usize = 0xB82;
-const MHPCOUNTERH3: usize = 0xB83; // MAX 31 (-3)
+const MHPCOUNTERH3: usize = 0xB83;
+const MHPCOUNTERH31: usize = 0xB9F;
+
+// Bits de cada contador en mcounteren, scounteren y mcountinhibit
+const COUNTER_CY: u32 = 1 << 0;
+const COUNTER_IR: u32 = 1 << 2;
+
+// Eventos que pueden contar mhpmcounter3..31. El valor que se escribe en
+// mhpmevent es el numero del evento, con 0 no se cuenta nada
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HpmEvent {
+    // Cargas y almacenamientos completados, incluidos los de coma flotante
+    Load = 1,
+    Store = 2,
+    // LR, SC y AMOs
+    Atomic = 3,
+    TakenBranch = 4,
+    // Traducciones de direcciones (de datos o instrucciones) que no estaban en
+    // la TLB
+    TlbMiss = 5,
+    // Excepciones e interrupciones atendidas
+    Exception = 6,
+    Interrupt = 7,
+}
+
+const HPM_EVENTS: usize = 8;
 
 // DEBUG
 const TSELECT: usize = 0x7A0;
@@ -58,15 +120,130 @@ pub const SCAUSE: usize = 0x142;
 pub const STVAL: usize = 0x143;
 pub const SIP: usize = 0x144;
 const SIP_MASK: u32 = 0xFFFF2222;
+const SIP_WRITE_MASK: u32 = 0x00002002;
+// Bits OF de mhpmeventh3..31, solo lectura (Sscofpmf)
+const SCOUNTOVF: usize = 0xDA0;
 
 pub const SATP: usize = 0x180;
+pub const SATP_ASID_MASK: u32 = 0x1FF;
+
+/*
+ * USER
+ */
+// FLOATING POINT
+pub const FFLAGS: usize = 0x001;
+pub const FRM: usize = 0x002;
+pub const FCSR: usize = 0x003;
+const FCSR_MASK: u32 = 0xFF;
+
+const FS_DIRTY: u32 = 0b11;
+
+// COUNTERS/TIMERS
+// Copias de solo lectura de los contadores de maquina, menos time que es el
+// mtime del CLINT
+const CYCLE: usize = 0xC00;
+const TIME: usize = 0xC01;
+const INSTRET: usize = 0xC02;
+const HPMCOUNTER31: usize = 0xC1F;
+const CYCLEH: usize = 0xC80;
+const TIMEH: usize = 0xC81;
+const INSTRETH: usize = 0xC82;
+const HPMCOUNTERH31: usize = 0xC9F;
+// CYCLE - MCYCLE
+const USER_COUNTER_OFFSET: usize = 0x100;
+
+// Nombres de los CSRs para la traza y el depurador
+pub const CSR_NAMES: [(&str, usize); 65] = [
+    ("fflags", FFLAGS),
+    ("frm", FRM),
+    ("fcsr", FCSR),
+    ("cycle", CYCLE),
+    ("time", TIME),
+    ("instret", INSTRET),
+    ("cycleh", CYCLEH),
+    ("timeh", TIMEH),
+    ("instreth", INSTRETH),
+    ("sstatus", SSTATUS),
+    ("sie", SIE),
+    ("stvec", STVEC),
+    ("scounteren", SCOUNTEREN),
+    ("scountovf", SCOUNTOVF),
+    ("sscratch", SSCRATCH),
+    ("sepc", SEPC),
+    ("scause", SCAUSE),
+    ("stval", STVAL),
+    ("sip", SIP),
+    ("satp", SATP),
+    ("mstatus", MSTATUS),
+    ("misa", MISA),
+    ("medeleg", MEDELEG),
+    ("mideleg", MIDELEG),
+    ("mie", MIE),
+    ("mtvec", MTVEC),
+    ("mcounteren", MCOUNTEREN),
+    ("menvcfg", MENVCFG),
+    ("menvcfgh", MENVCFGH),
+    ("mstatush", MSTATUSH),
+    ("medelegh", MEDELEGH),
+    ("mcountinhibit", MCOUNTINHIBIT),
+    ("mscratch", MSCRATCH),
+    ("mepc", MEPC),
+    ("mcause", MCAUSE),
+    ("mtval", MTVAL),
+    ("mip", MIP),
+    ("pmpcfg0", PMPCFG0),
+    ("pmpcfg1", PMPCFG0 + 1),
+    ("pmpcfg2", PMPCFG0 + 2),
+    ("pmpcfg3", PMPCFG3),
+    ("pmpaddr0", PMPADDR0),
+    ("pmpaddr1", PMPADDR0 + 1),
+    ("pmpaddr2", PMPADDR0 + 2),
+    ("pmpaddr3", PMPADDR0 + 3),
+    ("pmpaddr4", PMPADDR0 + 4),
+    ("pmpaddr5", PMPADDR0 + 5),
+    ("pmpaddr6", PMPADDR0 + 6),
+    ("pmpaddr7", PMPADDR0 + 7),
+    ("pmpaddr8", PMPADDR0 + 8),
+    ("pmpaddr9", PMPADDR0 + 9),
+    ("pmpaddr10", PMPADDR0 + 10),
+    ("pmpaddr11", PMPADDR0 + 11),
+    ("pmpaddr12", PMPADDR0 + 12),
+    ("pmpaddr13", PMPADDR0 + 13),
+    ("pmpaddr14", PMPADDR0 + 14),
+    ("pmpaddr15", PMPADDR15),
+    ("mcycle", MCYCLE),
+    ("minstret", MINSTRET),
+    ("mcycleh", MCYCLEH),
+    ("minstreth", MINSTRETH),
+    ("mvendorid", MVENDORID),
+    ("marchid", MARCHID),
+    ("mimpid", MIMPID),
+    ("mhartid", MHARTID),
+];
+
+pub fn get_csr_name(csr: usize) -> Option<&'static str> {
+    CSR_NAMES.iter().find(|(_, number)| *number == csr).map(|(name, _)| *name)
+}
+
+// pmpcfg0-15 y pmpaddr0-63
+pub fn is_pmp_csr(csr: usize) -> bool {
+    (PMPCFG0..=PMPADDR63).contains(&csr)
+}
 
 pub struct ControlAndStatus {
     csrs: [u32; 4096],
     // mstatus: MStatus,
     // satp: Satp32,
 
-    minstret_loaded: bool,
+    // Contadores escritos por la instruccion en curso, que no se incrementan al
+    // terminarla. Mismo formato que mcountinhibit
+    written_counters: u32,
+    // Contadores hpm que cuentan cada evento, con el formato de mcountinhibit.
+    // Se recalcula al escribir mhpmevent
+    event_counters: [u32; HPM_EVENTS],
+
+    // CSRs escritos por la instruccion en curso, solo con la traza activa
+    pub written_csrs: Option<Vec<usize>>,
 }
 
 impl ControlAndStatus {
@@ -78,6 +255,9 @@ impl ControlAndStatus {
         misa |= 1 << 18; // Supervisor ISA
         misa |= 1 << 12; // RV31M
         misa |= 1 << 8; // RV32I
+        misa |= 1 << 5; // RV32F
+        misa |= 1 << 3; // RV32D
+        misa |= 1 << 2; // RV32C
         misa |= 1; // RV32A
 
         csrs[MISA] = misa;
@@ -86,7 +266,9 @@ impl ControlAndStatus {
 
         Self {
             csrs,
-            minstret_loaded: false,
+            written_counters: 0,
+            event_counters: [0; HPM_EVENTS],
+            written_csrs: None,
         }
     }
 
@@ -121,6 +303,40 @@ impl ControlAndStatus {
                 self.csrs[SATP]
             }
 
+            SCOUNTOVF => {
+                let overflows = (3..32)
+                    .filter(|i| self.csrs[MHPMEVENTH3 + i - 3] & MHPMEVENTH_OF != 0)
+                    .fold(0, |overflows, i| overflows | (1 << i));
+
+                match priv_level {
+                    PrivilegeLevel::Machine => overflows,
+                    _ => overflows & self.csrs[MCOUNTEREN],
+                }
+            }
+
+            CYCLE..=HPMCOUNTER31 | CYCLEH..=HPMCOUNTERH31 => {
+                if !self.is_counter_enabled(csr, priv_level) {
+                    return Err(Exception::new(ExceptionType::IllegalInstruction, 0));
+                }
+
+                match csr {
+                    TIME | TIMEH => self.csrs[csr],
+                    _ => self.csrs[csr - USER_COUNTER_OFFSET],
+                }
+            }
+
+            FFLAGS | FRM | FCSR => {
+                if self.is_fs_off() {
+                    return Err(Exception::new(ExceptionType::IllegalInstruction, 0));
+                }
+
+                match csr {
+                    FFLAGS => self.csrs[FCSR] & 0x1F,
+                    FRM => (self.csrs[FCSR] >> 5) & 0x7,
+                    _ => self.csrs[FCSR] & FCSR_MASK,
+                }
+            }
+
             _ => self.csrs[csr],
         };
 
@@ -142,6 +358,14 @@ impl ControlAndStatus {
         Satp32(self.csrs[SATP])
     }
 
+    pub fn read_menvcfg_adue(&self) -> bool {
+        self.csrs[MENVCFGH] & MENVCFGH_ADUE != 0
+    }
+
+    pub fn read_misa(&self) -> Misa {
+        Misa(self.csrs[MISA])
+    }
+
     pub fn read_mstatus(&self, priv_level: PrivilegeLevel) -> Result<MStatus, Exception> {
         let csr = self.read_csr(MSTATUS, priv_level)?;
 
@@ -175,15 +399,44 @@ impl ControlAndStatus {
         }
 
         match csr {
-            MSTATUS => self.csrs[MSTATUS] = MSTATUS_MASK & val,
-            MINSTRET | MINSTRETH => {
-                self.minstret_loaded = true;
+            MSTATUS => {
+                self.csrs[MSTATUS] = MSTATUS_MASK & val;
+                self.update_sd();
+            }
+            MCYCLE..=MHPCOUNTER31 | MCYCLEH..=MHPCOUNTERH31 => {
+                self.written_counters |= 1 << (csr & 0x1F);
                 self.csrs[csr] = val;
             }
-            MEPC => self.csrs[MEPC] = val & 0xFFFFFFFC,
+            MCOUNTINHIBIT => self.csrs[MCOUNTINHIBIT] = val & MCOUNTINHIBIT_MASK,
+            MHPMEVENT3..=MHPMEVENT31 => {
+                self.csrs[csr] = val;
+                self.update_event_counters();
+            }
+            MHPMEVENTH3..=MHPMEVENTH31 => self.csrs[csr] = val & MHPMEVENTH_MASK,
+            MEPC => self.csrs[MEPC] = val & 0xFFFFFFFE,
+            MTVEC => self.csrs[MTVEC] = legalize_tvec(val),
+            MIP => self.csrs[MIP] = (self.csrs[MIP] & !MIP_WRITE_MASK) | (val & MIP_WRITE_MASK),
+            MIE => self.csrs[MIE] = val & MIE_MASK,
+            MENVCFG => self.csrs[MENVCFG] = val & MENVCFG_MASK,
+            MENVCFGH => self.csrs[MENVCFGH] = val & MENVCFGH_ADUE,
             MISA => self.csrs[MISA] = (self.csrs[MISA] & !MISA_MASK_WRITE) | (val & MISA_MASK_WRITE),
+            PMPCFG0..=PMPCFG3 => self.write_pmpcfg(csr, val),
+            PMPADDR0..=PMPADDR15 => {
+                if !is_pmpaddr_locked(self, csr - PMPADDR0) {
+                    self.csrs[csr] = val;
+                }
+            }
+            // Solo hay PMP_ENTRIES entradas, el resto valen 0
+            PMPCFG0..=PMPCFG15 | PMPADDR0..=PMPADDR63 => {}
 
-            SSTATUS => self.csrs[MSTATUS] = (self.csrs[MSTATUS] & !SSTATUS_MASK) | (val & SSTATUS_MASK),
+            SSTATUS => {
+                self.csrs[MSTATUS] = (self.csrs[MSTATUS] & !SSTATUS_MASK) | (val & SSTATUS_MASK);
+                self.update_sd();
+            }
+            SEPC => self.csrs[SEPC] = val & 0xFFFFFFFE,
+            STVEC => self.csrs[STVEC] = legalize_tvec(val),
+            SIP => self.csrs[MIP] = (self.csrs[MIP] & !SIP_WRITE_MASK) | (val & SIP_WRITE_MASK),
+            SIE => self.csrs[MIE] = (self.csrs[MIE] & !SIE_MASK) | (val & SIE_MASK),
 
             SATP => {
                 let mstatus = self.read_mstatus_unchecked();
@@ -195,26 +448,234 @@ impl ControlAndStatus {
                 self.csrs[SATP] = val;
             }
 
+            FFLAGS | FRM | FCSR => {
+                if self.is_fs_off() {
+                    return Err(Exception::new(ExceptionType::IllegalInstruction, 0));
+                }
+
+                self.csrs[FCSR] = match csr {
+                    FFLAGS => (self.csrs[FCSR] & !0x1F) | (val & 0x1F),
+                    FRM => (self.csrs[FCSR] & !(0x7 << 5)) | ((val & 0x7) << 5),
+                    _ => val & FCSR_MASK,
+                };
+                self.set_fs_dirty();
+            }
+
             _ => self.csrs[csr] = val,
         }
 
+        self.log_write(csr);
+
         Ok(())
     }
 
-    pub fn increment_minstret(&mut self) {
-        if self.minstret_loaded {
-            self.minstret_loaded = false;
+    // Cada byte de pmpcfg configura una entrada. Las bloqueadas no cambian
+    fn write_pmpcfg(&mut self, csr: usize, val: u32) {
+        let old = self.csrs[csr].to_le_bytes();
+        let new = val.to_le_bytes();
+
+        let bytes: [u8; 4] = std::array::from_fn(|i| {
+            if PmpConfig(old[i]).get_l() {
+                old[i]
+            } else {
+                PmpConfig::legalize(new[i]).0
+            }
+        });
+
+        self.csrs[csr] = u32::from_le_bytes(bytes);
+    }
+
+    pub fn read_pmpcfg(&self, entry: usize) -> PmpConfig {
+        PmpConfig(self.csrs[PMPCFG0 + entry / 4].to_le_bytes()[entry % 4])
+    }
+
+    pub fn read_pmpaddr(&self, entry: usize) -> u32 {
+        self.csrs[PMPADDR0 + entry]
+    }
+
+    fn log_write(&mut self, csr: usize) {
+        if let Some(written_csrs) = &mut self.written_csrs {
+            written_csrs.push(csr);
+        }
+    }
+
+    pub fn is_fs_off(&self) -> bool {
+        self.read_mstatus_unchecked().get_fs() == 0
+    }
+
+    pub fn set_fs_dirty(&mut self) {
+        let mut mstatus = self.read_mstatus_unchecked();
+        mstatus.set_fs(FS_DIRTY);
+        mstatus.set_sd(true);
+
+        self.csrs[MSTATUS] = mstatus.0;
+    }
+
+    // SD es de solo lectura y resume si FS, VS o XS estan sucios
+    fn update_sd(&mut self) {
+        let mut mstatus = self.read_mstatus_unchecked();
+        let dirty = mstatus.get_fs() == FS_DIRTY || mstatus.get_vs() == FS_DIRTY || mstatus.get_xs() == FS_DIRTY;
+        mstatus.set_sd(dirty);
+
+        self.csrs[MSTATUS] = mstatus.0;
+    }
+
+    pub fn read_frm(&self) -> u32 {
+        (self.csrs[FCSR] >> 5) & 0x7
+    }
+
+    pub fn accrue_fflags(&mut self, flags: u32) {
+        self.csrs[FCSR] |= flags & 0x1F;
+        self.set_fs_dirty();
+
+        if flags & 0x1F != 0 {
+            self.log_write(FFLAGS);
+        }
+    }
+
+    // Los dispositivos activan y desactivan los bits de mip con esta funcion,
+    // las escrituras de software solo pueden modificar los bits de MIP_WRITE_MASK
+    pub fn set_interrupt_pending(&mut self, int_type: InterruptType, pending: bool) {
+        let bit = 1 << (int_type as u32);
+
+        if pending {
+            self.csrs[MIP] |= bit;
+        } else {
+            self.csrs[MIP] &= !bit;
+        }
+    }
+
+    pub fn is_interrupt_enabled(&self, int_type: InterruptType) -> bool {
+        self.csrs[MIE] & (1 << (int_type as u32)) != 0
+    }
+
+    // Interrupciones pendientes y habilitadas (mip & mie)
+    pub fn read_enabled_interrupts(&self) -> u32 {
+        self.csrs[MIP] & self.csrs[MIE] & MIP_MASK
+    }
+
+    // Los modos inferiores solo pueden leer los contadores habilitados en
+    // mcounteren y, en modo usuario, tambien en scounteren
+    fn is_counter_enabled(&self, csr: usize, priv_level: PrivilegeLevel) -> bool {
+        let bit = 1 << (csr & 0x1F);
+
+        match priv_level {
+            PrivilegeLevel::Machine => true,
+            PrivilegeLevel::Supervisor => self.csrs[MCOUNTEREN] & bit != 0,
+            PrivilegeLevel::User => self.csrs[MCOUNTEREN] & self.csrs[SCOUNTEREN] & bit != 0,
+        }
+    }
+
+    // El CLINT lleva mtime, time es una copia que se actualiza antes de cada
+    // instruccion
+    pub fn set_time(&mut self, mtime: u64) {
+        self.csrs[TIME] = mtime as u32;
+        self.csrs[TIMEH] = (mtime >> 32) as u32;
+    }
+
+    // Se llama al terminar cada instruccion, aunque provoque una excepcion, y en
+    // cada ciclo que el hart pasa en wfi. mcycle avanza siempre y minstret solo si
+    // la instruccion se ha completado. Los contadores inhibidos en mcountinhibit
+    // o escritos por la propia instruccion no cambian
+    pub fn increment_counters(&mut self, retired: bool) {
+        let stopped = self.csrs[MCOUNTINHIBIT] | std::mem::take(&mut self.written_counters);
+
+        if stopped & COUNTER_CY == 0 {
+            self.increment_counter(MCYCLE, MCYCLEH);
+        }
+
+        if retired && stopped & COUNTER_IR == 0 {
+            self.increment_counter(MINSTRET, MINSTRETH);
+        }
+    }
+
+    // Devuelve true si el contador ha desbordado
+    fn increment_counter(&mut self, low: usize, high: usize) -> bool {
+        let val = (self.csrs[low] as u64) | ((self.csrs[high] as u64) << 32);
+        let new_val = val.wrapping_add(1);
+
+        self.csrs[low] = new_val as u32;
+        self.csrs[high] = (new_val >> 32) as u32;
+
+        new_val == 0
+    }
+
+    // Incrementa los contadores hpm que cuentan el evento en el modo de
+    // privilegio actual. Si alguno desborda con OF a 0 se activa OF y se pide
+    // la interrupcion de desbordamiento (LCOFI)
+    pub fn count_event(&mut self, event: HpmEvent, priv_level: PrivilegeLevel) {
+        let counters = self.event_counters[event as usize] & !(self.csrs[MCOUNTINHIBIT] | self.written_counters);
+
+        if counters == 0 {
             return;
         }
 
-        let minstret = self.csrs[MINSTRET];
-        let minstreth = self.csrs[MINSTRETH];
+        let inhibit = match priv_level {
+            PrivilegeLevel::Machine => MHPMEVENTH_MINH,
+            PrivilegeLevel::Supervisor => MHPMEVENTH_SINH,
+            PrivilegeLevel::User => MHPMEVENTH_UINH,
+        };
+
+        for i in (3..32).filter(|i| counters & (1 << i) != 0) {
+            let eventh = MHPMEVENTH3 + i - 3;
+
+            if self.csrs[eventh] & inhibit != 0 {
+                continue;
+            }
+
+            let overflow = self.increment_counter(MHPCOUNTER3 + i - 3, MHPCOUNTERH3 + i - 3);
+
+            if overflow && self.csrs[eventh] & MHPMEVENTH_OF == 0 {
+                self.csrs[eventh] |= MHPMEVENTH_OF;
+                self.set_interrupt_pending(InterruptType::CounterOverflowInt, true);
+            }
+        }
+    }
+
+    fn update_event_counters(&mut self) {
+        self.event_counters = [0; HPM_EVENTS];
+
+        for i in 3..32 {
+            let event = self.csrs[MHPMEVENT3 + i - 3] as usize;
+
+            if let Some(counters) = self.event_counters.get_mut(event) {
+                *counters |= 1 << i;
+            }
+        }
+
+        // El evento 0 no cuenta nada
+        self.event_counters[0] = 0;
+    }
+
+    // Se guardan los 4096 CSRs tal cual, sin pasar por las mascaras de escritura
+    pub fn save_state(&self, writer: &mut SnapshotWriter) {
+        for csr in self.csrs {
+            writer.write_u32(csr);
+        }
+
+        writer.write_u32(self.written_counters);
+    }
+
+    pub fn load_state(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        for csr in &mut self.csrs {
+            *csr = reader.read_u32()?;
+        }
 
-        let minsret_64 = (minstret as u64) + ((minstreth as u64) << 32);
-        let new_minstret = minsret_64.wrapping_add(1);
+        self.written_counters = reader.read_u32()?;
+        self.update_event_counters();
 
-        self.csrs[MINSTRET] = new_minstret as u32;
-        self.csrs[MINSTRETH] = (new_minstret >> 32) as u32;
+        Ok(())
+    }
+}
+
+// MODE de mtvec/stvec: 0 directo, 1 vectorizado. Los valores reservados (>= 2)
+// se tratan como modo directo
+fn legalize_tvec(val: u32) -> u32 {
+    let base = val & TVEC_BASE_MASK;
+
+    match val & TVEC_MODE_MASK {
+        TVEC_MODE_VECTORED => base | TVEC_MODE_VECTORED,
+        _ => base,
     }
 }
 
@@ -245,6 +706,19 @@ bitfield! {
     pub get_sd, set_sd: 31;
 }
 
+bitfield! {
+    pub struct Misa(u32);
+    pub get_a, _: 0;
+    pub get_c, _: 2;
+    pub get_d, _: 3;
+    pub get_f, _: 5;
+    pub get_i, _: 8;
+    pub get_m, _: 12;
+    pub get_s, _: 18;
+    pub get_u, _: 20;
+    pub get_mxl, _: 31, 30;
+}
+
 bitfield! {
     pub struct Satp32(u32);
     u32;
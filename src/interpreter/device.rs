@@ -0,0 +1,82 @@
+// Dispositivos mapeados en memoria. El bus les pasa el desplazamiento del
+// acceso dentro de su region y el tamaño del acceso. Los accesos siempre estan
+// alineados a su tamaño y no se salen de la region.
+//
+// Solo hace falta implementar los accesos de un byte. Los de 16, 32 y 64 bits
+// se hacen por defecto con accesos mas pequeños en little endian, asi que un
+// dispositivo que solo admita palabras (como el PLIC) tiene que implementar los
+// de 32 bits y hacer fallar los de un byte.
+
+use std::fmt;
+
+use crate::interpreter::snapshot::{SnapshotError, SnapshotReader, SnapshotWriter};
+
+// El dispositivo no admite el acceso. El bus lo convierte en un fallo de acceso
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BusError;
+
+impl fmt::Display for BusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "acceso no admitido por el dispositivo")
+    }
+}
+
+pub trait Device {
+    fn read_u8(&mut self, offset: u64) -> Result<u8, BusError>;
+
+    fn write_u8(&mut self, offset: u64, val: u8) -> Result<(), BusError>;
+
+    fn read_u16(&mut self, offset: u64) -> Result<u16, BusError> {
+        Ok(u16::from_le_bytes([self.read_u8(offset)?, self.read_u8(offset + 1)?]))
+    }
+
+    fn read_u32(&mut self, offset: u64) -> Result<u32, BusError> {
+        let low = self.read_u16(offset)? as u32;
+        let high = self.read_u16(offset + 2)? as u32;
+
+        Ok((high << 16) | low)
+    }
+
+    fn read_u64(&mut self, offset: u64) -> Result<u64, BusError> {
+        let low = self.read_u32(offset)? as u64;
+        let high = self.read_u32(offset + 4)? as u64;
+
+        Ok((high << 32) | low)
+    }
+
+    fn write_u16(&mut self, offset: u64, val: u16) -> Result<(), BusError> {
+        let bytes = val.to_le_bytes();
+        self.write_u8(offset, bytes[0])?;
+        self.write_u8(offset + 1, bytes[1])
+    }
+
+    fn write_u32(&mut self, offset: u64, val: u32) -> Result<(), BusError> {
+        self.write_u16(offset, val as u16)?;
+        self.write_u16(offset + 2, (val >> 16) as u16)
+    }
+
+    fn write_u64(&mut self, offset: u64, val: u64) -> Result<(), BusError> {
+        self.write_u32(offset, val as u32)?;
+        self.write_u32(offset + 4, (val >> 32) as u32)
+    }
+
+    // Vuelve al estado de reset. El contenido de las memorias se mantiene
+    fn reset(&mut self) {}
+
+    // Se llama una vez por cada instruccion ejecutada
+    fn tick(&mut self) {}
+
+    // Devuelve true si el dispositivo ha activado su linea de interrupcion desde
+    // la ultima llamada. El bus la pasa al PLIC si se ha registrado con una
+    fn take_interrupt(&mut self) -> bool {
+        false
+    }
+
+    // Estado que se guarda en las instantaneas. load_state tiene que leer
+    // exactamente lo que escribe save_state
+    fn save_state(&self, _writer: &mut SnapshotWriter) {}
+
+    fn load_state(&mut self, _reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,554 @@
+// Desensamblador de RV32IMAFDC + Zicsr + Zifencei y las instrucciones
+// privilegiadas. Usa los nombres ABI de los registros y las pseudoinstrucciones
+// habituales (li, mv, ret, beqz, csrr...). Los saltos se muestran relativos a pc
+// (pc + 16) y las instrucciones comprimidas se muestran como la instruccion de
+// 32 bits a la que se expanden.
+
+use crate::interpreter::{
+    csr::get_csr_name,
+    extensions::rv32c::{expand_compressed, is_compressed},
+};
+
+pub const X_REG_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7",
+    "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+];
+
+pub const F_REG_NAMES: [&str; 32] = [
+    "ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7", "fs0", "fs1", "fa0", "fa1", "fa2", "fa3", "fa4", "fa5",
+    "fa6", "fa7", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7", "fs8", "fs9", "fs10", "fs11", "ft8", "ft9", "ft10", "ft11",
+];
+
+const ROUNDING_MODES: [&str; 8] = ["rne", "rtz", "rdn", "rup", "rmm", "5", "6", "dyn"];
+const RM_DYN: u32 = 7;
+
+const UNKNOWN: &str = "unknown";
+
+// Si los dos bits bajos no son 0b11 solo se usan los 16 bits bajos
+pub fn disassemble(instr: u32) -> String {
+    if !is_compressed(instr) {
+        return disassemble_32(instr);
+    }
+
+    match instr as u16 {
+        0 => "unimp".to_string(),
+        compressed => expand_compressed(compressed).map_or_else(|| UNKNOWN.to_string(), disassemble_32),
+    }
+}
+
+fn disassemble_32(instr: u32) -> String {
+    let opcode = instr & 0x7F;
+
+    let text = match opcode {
+        0b0110111 => Some(format_instr("lui", &[x(rd(instr)), format!("{:#x}", instr >> 12)])),
+        0b0010111 => Some(format_instr("auipc", &[x(rd(instr)), format!("{:#x}", instr >> 12)])),
+        0b1101111 => Some(disassemble_jal(instr)),
+        0b1100111 => disassemble_jalr(instr),
+        0b1100011 => disassemble_branch(instr),
+        0b0000011 => disassemble_load(instr),
+        0b0100011 => disassemble_store(instr),
+        0b0010011 => disassemble_op_imm(instr),
+        0b0110011 => disassemble_op(instr),
+        0b0001111 => disassemble_fence(instr),
+        0b1110011 => disassemble_system(instr),
+        0b0101111 => disassemble_atomic(instr),
+        0b0000111 => disassemble_load_fp(instr),
+        0b0100111 => disassemble_store_fp(instr),
+        0b1000011 | 0b1000111 | 0b1001011 | 0b1001111 => disassemble_fused_fp(instr),
+        0b1010011 => disassemble_op_fp(instr),
+        _ => None,
+    };
+
+    text.unwrap_or_else(|| UNKNOWN.to_string())
+}
+
+fn disassemble_jal(instr: u32) -> String {
+    let offset = j_imm(instr);
+
+    match rd(instr) {
+        0 => format_instr("j", &[pc_offset(offset)]),
+        1 => format_instr("jal", &[pc_offset(offset)]),
+        rd => format_instr("jal", &[x(rd), pc_offset(offset)]),
+    }
+}
+
+fn disassemble_jalr(instr: u32) -> Option<String> {
+    if funct3(instr) != 0 {
+        return None;
+    }
+
+    let text = match (rd(instr), rs1(instr), i_imm(instr)) {
+        (0, 1, 0) => "ret".to_string(),
+        (0, rs1, 0) => format_instr("jr", &[x(rs1)]),
+        (1, rs1, 0) => format_instr("jalr", &[x(rs1)]),
+        (rd, rs1, imm) => format_instr("jalr", &[x(rd), mem_operand(imm, rs1)]),
+    };
+
+    Some(text)
+}
+
+fn disassemble_branch(instr: u32) -> Option<String> {
+    let rs1 = rs1(instr);
+    let rs2 = rs2(instr);
+    let offset = pc_offset(b_imm(instr));
+
+    let mnemonic = match funct3(instr) {
+        0b000 => "beq",
+        0b001 => "bne",
+        0b100 => "blt",
+        0b101 => "bge",
+        0b110 => "bltu",
+        0b111 => "bgeu",
+        _ => return None,
+    };
+
+    let text = match (mnemonic, rs1, rs2) {
+        ("beq", _, 0) => format_instr("beqz", &[x(rs1), offset]),
+        ("bne", _, 0) => format_instr("bnez", &[x(rs1), offset]),
+        ("blt", _, 0) => format_instr("bltz", &[x(rs1), offset]),
+        ("bge", _, 0) => format_instr("bgez", &[x(rs1), offset]),
+        ("blt", 0, _) => format_instr("bgtz", &[x(rs2), offset]),
+        ("bge", 0, _) => format_instr("blez", &[x(rs2), offset]),
+        _ => format_instr(mnemonic, &[x(rs1), x(rs2), offset]),
+    };
+
+    Some(text)
+}
+
+fn disassemble_load(instr: u32) -> Option<String> {
+    let mnemonic = match funct3(instr) {
+        0b000 => "lb",
+        0b001 => "lh",
+        0b010 => "lw",
+        0b100 => "lbu",
+        0b101 => "lhu",
+        _ => return None,
+    };
+
+    Some(format_instr(mnemonic, &[x(rd(instr)), mem_operand(i_imm(instr), rs1(instr))]))
+}
+
+fn disassemble_store(instr: u32) -> Option<String> {
+    let mnemonic = match funct3(instr) {
+        0b000 => "sb",
+        0b001 => "sh",
+        0b010 => "sw",
+        _ => return None,
+    };
+
+    Some(format_instr(mnemonic, &[x(rs2(instr)), mem_operand(s_imm(instr), rs1(instr))]))
+}
+
+fn disassemble_op_imm(instr: u32) -> Option<String> {
+    let rd = rd(instr);
+    let rs1 = rs1(instr);
+    let imm = i_imm(instr);
+    let shamt = (instr >> 20) & 0x1F;
+
+    let text = match (funct3(instr), funct7(instr)) {
+        (0b000, _) => match (rd, rs1, imm) {
+            (0, 0, 0) => "nop".to_string(),
+            (_, 0, _) => format_instr("li", &[x(rd), imm.to_string()]),
+            (_, _, 0) => format_instr("mv", &[x(rd), x(rs1)]),
+            _ => format_instr("addi", &[x(rd), x(rs1), imm.to_string()]),
+        },
+        (0b010, _) => format_instr("slti", &[x(rd), x(rs1), imm.to_string()]),
+        (0b011, _) if imm == 1 => format_instr("seqz", &[x(rd), x(rs1)]),
+        (0b011, _) => format_instr("sltiu", &[x(rd), x(rs1), imm.to_string()]),
+        (0b100, _) if imm == -1 => format_instr("not", &[x(rd), x(rs1)]),
+        (0b100, _) => format_instr("xori", &[x(rd), x(rs1), imm.to_string()]),
+        (0b110, _) => format_instr("ori", &[x(rd), x(rs1), imm.to_string()]),
+        (0b111, _) => format_instr("andi", &[x(rd), x(rs1), imm.to_string()]),
+        (0b001, 0x00) => format_instr("slli", &[x(rd), x(rs1), shamt.to_string()]),
+        (0b101, 0x00) => format_instr("srli", &[x(rd), x(rs1), shamt.to_string()]),
+        (0b101, 0x20) => format_instr("srai", &[x(rd), x(rs1), shamt.to_string()]),
+        _ => return None,
+    };
+
+    Some(text)
+}
+
+fn disassemble_op(instr: u32) -> Option<String> {
+    let rd = rd(instr);
+    let rs1 = rs1(instr);
+    let rs2 = rs2(instr);
+
+    let mnemonic = match (funct7(instr), funct3(instr)) {
+        (0x00, 0b000) => "add",
+        (0x20, 0b000) => "sub",
+        (0x00, 0b001) => "sll",
+        (0x00, 0b010) => "slt",
+        (0x00, 0b011) => "sltu",
+        (0x00, 0b100) => "xor",
+        (0x00, 0b101) => "srl",
+        (0x20, 0b101) => "sra",
+        (0x00, 0b110) => "or",
+        (0x00, 0b111) => "and",
+
+        (0x01, 0b000) => "mul",
+        (0x01, 0b001) => "mulh",
+        (0x01, 0b010) => "mulhsu",
+        (0x01, 0b011) => "mulhu",
+        (0x01, 0b100) => "div",
+        (0x01, 0b101) => "divu",
+        (0x01, 0b110) => "rem",
+        (0x01, 0b111) => "remu",
+        _ => return None,
+    };
+
+    let text = match (mnemonic, rs1, rs2) {
+        ("add", 0, _) => format_instr("mv", &[x(rd), x(rs2)]),
+        ("sub", 0, _) => format_instr("neg", &[x(rd), x(rs2)]),
+        ("sltu", 0, _) => format_instr("snez", &[x(rd), x(rs2)]),
+        ("slt", _, 0) => format_instr("sltz", &[x(rd), x(rs1)]),
+        ("slt", 0, _) => format_instr("sgtz", &[x(rd), x(rs2)]),
+        _ => format_instr(mnemonic, &[x(rd), x(rs1), x(rs2)]),
+    };
+
+    Some(text)
+}
+
+fn disassemble_fence(instr: u32) -> Option<String> {
+    let pred = (instr >> 24) & 0xF;
+    let succ = (instr >> 20) & 0xF;
+    let fm = instr >> 28;
+
+    let text = match funct3(instr) {
+        0b000 if fm == 0b1000 && pred == 0b0011 && succ == 0b0011 => "fence.tso".to_string(),
+        0b000 if pred == 0b1111 && succ == 0b1111 => "fence".to_string(),
+        0b000 => format_instr("fence", &[fence_set(pred), fence_set(succ)]),
+        0b001 => "fence.i".to_string(),
+        _ => return None,
+    };
+
+    Some(text)
+}
+
+fn disassemble_system(instr: u32) -> Option<String> {
+    let rd = rd(instr);
+    let rs1 = rs1(instr);
+    let csr = (instr >> 20) as usize;
+    let csr_name = get_csr_name(csr).map_or_else(|| format!("{csr:#x}"), str::to_string);
+
+    let text = match funct3(instr) {
+        0b000 => match instr {
+            0x00000073 => "ecall".to_string(),
+            0x00100073 => "ebreak".to_string(),
+            0x10200073 => "sret".to_string(),
+            0x30200073 => "mret".to_string(),
+            0x10500073 => "wfi".to_string(),
+            _ if funct7(instr) == 0x09 && rd == 0 => match (rs1, rs2(instr)) {
+                (0, 0) => "sfence.vma".to_string(),
+                (rs1, 0) => format_instr("sfence.vma", &[x(rs1)]),
+                (rs1, rs2) => format_instr("sfence.vma", &[x(rs1), x(rs2)]),
+            },
+            _ => return None,
+        },
+        // csrrw zero, cycle, zero
+        0b001 if instr == 0xC0001073 => "unimp".to_string(),
+        0b001 if rd == 0 => format_instr("csrw", &[csr_name, x(rs1)]),
+        0b001 => format_instr("csrrw", &[x(rd), csr_name, x(rs1)]),
+        0b010 if rs1 == 0 => format_instr("csrr", &[x(rd), csr_name]),
+        0b010 if rd == 0 => format_instr("csrs", &[csr_name, x(rs1)]),
+        0b010 => format_instr("csrrs", &[x(rd), csr_name, x(rs1)]),
+        0b011 if rd == 0 => format_instr("csrc", &[csr_name, x(rs1)]),
+        0b011 => format_instr("csrrc", &[x(rd), csr_name, x(rs1)]),
+        0b101 if rd == 0 => format_instr("csrwi", &[csr_name, rs1.to_string()]),
+        0b101 => format_instr("csrrwi", &[x(rd), csr_name, rs1.to_string()]),
+        0b110 if rd == 0 => format_instr("csrsi", &[csr_name, rs1.to_string()]),
+        0b110 => format_instr("csrrsi", &[x(rd), csr_name, rs1.to_string()]),
+        0b111 if rd == 0 => format_instr("csrci", &[csr_name, rs1.to_string()]),
+        0b111 => format_instr("csrrci", &[x(rd), csr_name, rs1.to_string()]),
+        _ => return None,
+    };
+
+    Some(text)
+}
+
+fn disassemble_atomic(instr: u32) -> Option<String> {
+    if funct3(instr) != 0b010 {
+        return None;
+    }
+
+    let name = match instr >> 27 {
+        0x02 if rs2(instr) == 0 => "lr",
+        0x03 => "sc",
+        0x01 => "amoswap",
+        0x00 => "amoadd",
+        0x04 => "amoxor",
+        0x0C => "amoand",
+        0x08 => "amoor",
+        0x10 => "amomin",
+        0x14 => "amomax",
+        0x18 => "amominu",
+        0x1C => "amomaxu",
+        _ => return None,
+    };
+
+    let ordering = match (instr >> 25) & 0b11 {
+        0b10 => ".aq",
+        0b01 => ".rl",
+        0b11 => ".aqrl",
+        _ => "",
+    };
+
+    let mnemonic = format!("{name}.w{ordering}");
+    let address = format!("({})", x(rs1(instr)));
+
+    if name == "lr" {
+        Some(format_instr(&mnemonic, &[x(rd(instr)), address]))
+    } else {
+        Some(format_instr(&mnemonic, &[x(rd(instr)), x(rs2(instr)), address]))
+    }
+}
+
+fn disassemble_load_fp(instr: u32) -> Option<String> {
+    let mnemonic = match funct3(instr) {
+        0b010 => "flw",
+        0b011 => "fld",
+        _ => return None,
+    };
+
+    Some(format_instr(mnemonic, &[f(rd(instr)), mem_operand(i_imm(instr), rs1(instr))]))
+}
+
+fn disassemble_store_fp(instr: u32) -> Option<String> {
+    let mnemonic = match funct3(instr) {
+        0b010 => "fsw",
+        0b011 => "fsd",
+        _ => return None,
+    };
+
+    Some(format_instr(mnemonic, &[f(rs2(instr)), mem_operand(s_imm(instr), rs1(instr))]))
+}
+
+fn disassemble_fused_fp(instr: u32) -> Option<String> {
+    let name = match instr & 0x7F {
+        0b1000011 => "fmadd",
+        0b1000111 => "fmsub",
+        0b1001011 => "fnmsub",
+        _ => "fnmadd",
+    };
+
+    let precision = fp_precision((instr >> 25) & 0b11)?;
+    let rs3 = instr >> 27;
+
+    let mut operands = vec![f(rd(instr)), f(rs1(instr)), f(rs2(instr)), f(rs3)];
+    push_rounding_mode(&mut operands, instr);
+
+    Some(format_instr(&format!("{name}.{precision}"), &operands))
+}
+
+fn disassemble_op_fp(instr: u32) -> Option<String> {
+    let rd = rd(instr);
+    let rs1 = rs1(instr);
+    let rs2 = rs2(instr);
+    let funct3 = funct3(instr);
+    let funct7 = funct7(instr);
+    let precision = fp_precision(funct7 & 0b11)?;
+
+    let with_rm = |mnemonic: &str, mut operands: Vec<String>| {
+        push_rounding_mode(&mut operands, instr);
+        format_instr(mnemonic, &operands)
+    };
+
+    let text = match (funct7 >> 2, funct3, rs2) {
+        (0b00000, _, _) => with_rm(&format!("fadd.{precision}"), vec![f(rd), f(rs1), f(rs2)]),
+        (0b00001, _, _) => with_rm(&format!("fsub.{precision}"), vec![f(rd), f(rs1), f(rs2)]),
+        (0b00010, _, _) => with_rm(&format!("fmul.{precision}"), vec![f(rd), f(rs1), f(rs2)]),
+        (0b00011, _, _) => with_rm(&format!("fdiv.{precision}"), vec![f(rd), f(rs1), f(rs2)]),
+        (0b01011, _, 0) => with_rm(&format!("fsqrt.{precision}"), vec![f(rd), f(rs1)]),
+
+        (0b00100, 0b000, _) if rs1 == rs2 => format_instr(&format!("fmv.{precision}"), &[f(rd), f(rs1)]),
+        (0b00100, 0b001, _) if rs1 == rs2 => format_instr(&format!("fneg.{precision}"), &[f(rd), f(rs1)]),
+        (0b00100, 0b010, _) if rs1 == rs2 => format_instr(&format!("fabs.{precision}"), &[f(rd), f(rs1)]),
+        (0b00100, 0b000, _) => format_instr(&format!("fsgnj.{precision}"), &[f(rd), f(rs1), f(rs2)]),
+        (0b00100, 0b001, _) => format_instr(&format!("fsgnjn.{precision}"), &[f(rd), f(rs1), f(rs2)]),
+        (0b00100, 0b010, _) => format_instr(&format!("fsgnjx.{precision}"), &[f(rd), f(rs1), f(rs2)]),
+
+        (0b00101, 0b000, _) => format_instr(&format!("fmin.{precision}"), &[f(rd), f(rs1), f(rs2)]),
+        (0b00101, 0b001, _) => format_instr(&format!("fmax.{precision}"), &[f(rd), f(rs1), f(rs2)]),
+
+        // fcvt.s.d y fcvt.d.s. Las conversiones a doble son exactas y no usan rm
+        (0b01000, _, 1) if precision == "s" => with_rm("fcvt.s.d", vec![f(rd), f(rs1)]),
+        (0b01000, _, 0) if precision == "d" => format_instr("fcvt.d.s", &[f(rd), f(rs1)]),
+
+        (0b10100, 0b010, _) => format_instr(&format!("feq.{precision}"), &[x(rd), f(rs1), f(rs2)]),
+        (0b10100, 0b001, _) => format_instr(&format!("flt.{precision}"), &[x(rd), f(rs1), f(rs2)]),
+        (0b10100, 0b000, _) => format_instr(&format!("fle.{precision}"), &[x(rd), f(rs1), f(rs2)]),
+
+        (0b11000, _, 0) => with_rm(&format!("fcvt.w.{precision}"), vec![x(rd), f(rs1)]),
+        (0b11000, _, 1) => with_rm(&format!("fcvt.wu.{precision}"), vec![x(rd), f(rs1)]),
+        (0b11010, _, 0) if precision == "d" => format_instr("fcvt.d.w", &[f(rd), x(rs1)]),
+        (0b11010, _, 1) if precision == "d" => format_instr("fcvt.d.wu", &[f(rd), x(rs1)]),
+        (0b11010, _, 0) => with_rm("fcvt.s.w", vec![f(rd), x(rs1)]),
+        (0b11010, _, 1) => with_rm("fcvt.s.wu", vec![f(rd), x(rs1)]),
+
+        (0b11100, 0b000, 0) if precision == "s" => format_instr("fmv.x.w", &[x(rd), f(rs1)]),
+        (0b11100, 0b001, 0) => format_instr(&format!("fclass.{precision}"), &[x(rd), f(rs1)]),
+        (0b11110, 0b000, 0) if precision == "s" => format_instr("fmv.w.x", &[f(rd), x(rs1)]),
+        _ => return None,
+    };
+
+    Some(text)
+}
+
+fn fp_precision(fmt: u32) -> Option<&'static str> {
+    match fmt {
+        0b00 => Some("s"),
+        0b01 => Some("d"),
+        _ => None,
+    }
+}
+
+// El modo de redondeo solo se muestra si no es el dinamico
+fn push_rounding_mode(operands: &mut Vec<String>, instr: u32) {
+    let rm = funct3(instr);
+
+    if rm != RM_DYN {
+        operands.push(ROUNDING_MODES[rm as usize].to_string());
+    }
+}
+
+fn format_instr(mnemonic: &str, operands: &[String]) -> String {
+    format!("{mnemonic:<7} {}", operands.join(", "))
+}
+
+fn mem_operand(offset: i32, base: u32) -> String {
+    format!("{offset}({})", x(base))
+}
+
+fn pc_offset(offset: i32) -> String {
+    if offset < 0 {
+        format!("pc - {}", offset.unsigned_abs())
+    } else {
+        format!("pc + {offset}")
+    }
+}
+
+fn fence_set(bits: u32) -> String {
+    let set: String = [(8, 'i'), (4, 'o'), (2, 'r'), (1, 'w')]
+        .iter()
+        .filter(|(bit, _)| bits & bit != 0)
+        .map(|(_, name)| *name)
+        .collect();
+
+    if set.is_empty() { "0".to_string() } else { set }
+}
+
+fn x(reg: u32) -> String {
+    X_REG_NAMES[reg as usize].to_string()
+}
+
+fn f(reg: u32) -> String {
+    F_REG_NAMES[reg as usize].to_string()
+}
+
+fn rd(instr: u32) -> u32 {
+    (instr >> 7) & 0x1F
+}
+
+fn rs1(instr: u32) -> u32 {
+    (instr >> 15) & 0x1F
+}
+
+fn rs2(instr: u32) -> u32 {
+    (instr >> 20) & 0x1F
+}
+
+fn funct3(instr: u32) -> u32 {
+    (instr >> 12) & 0x7
+}
+
+fn funct7(instr: u32) -> u32 {
+    instr >> 25
+}
+
+fn i_imm(instr: u32) -> i32 {
+    (instr as i32) >> 20
+}
+
+fn s_imm(instr: u32) -> i32 {
+    (((instr & 0xFE000000) as i32) >> 20) | ((instr >> 7) & 0x1F) as i32
+}
+
+fn b_imm(instr: u32) -> i32 {
+    (((instr & 0x80000000) as i32) >> 19)
+        | (((instr >> 7) & 0x1) << 11) as i32
+        | (((instr >> 25) & 0x3F) << 5) as i32
+        | (((instr >> 8) & 0xF) << 1) as i32
+}
+
+fn j_imm(instr: u32) -> i32 {
+    (((instr & 0x80000000) as i32) >> 11)
+        | (instr & 0xFF000) as i32
+        | (((instr >> 20) & 0x1) << 11) as i32
+        | (((instr >> 21) & 0x3FF) << 1) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::disassemble;
+
+    // Codificacion y texto esperado, comprobados con llvm-mc
+    const CASES: [(u32, &str); 30] = [
+        // Pseudoinstrucciones
+        (0x00500513, "li      a0, 5"),
+        (0x00058513, "mv      a0, a1"),
+        (0x00000013, "nop"),
+        (0x00008067, "ret"),
+        (0x00050863, "beqz    a0, pc + 16"),
+        (0xff9ff0ef, "jal     pc - 8"),
+        // Cargas, almacenamientos y operaciones de enteros
+        (0x00812503, "lw      a0, 8(sp)"),
+        (0xfeb42e23, "sw      a1, -4(s0)"),
+        (0x12345537, "lui     a0, 0x12345"),
+        (0x40c58533, "sub     a0, a1, a2"),
+        (0x02c58533, "mul     a0, a1, a2"),
+        // CSRs con nombre y sin el
+        (0x30002573, "csrr    a0, mstatus"),
+        (0x30529073, "csrw    mtvec, t0"),
+        (0xc0002573, "csrr    a0, cycle"),
+        (0x7c00d073, "csrwi   0x7c0, 1"),
+        // Modos de redondeo: dyn no se muestra
+        (0x00c58553, "fadd.s  fa0, fa1, fa2, rne"),
+        (0x00c5f553, "fadd.s  fa0, fa1, fa2"),
+        (0x6ac59543, "fmadd.d fa0, fa1, fa2, fa3, rtz"),
+        (0xc0051553, "fcvt.w.s a0, fa0, rtz"),
+        (0x22b58553, "fmv.d   fa0, fa1"),
+        (0x20b5a553, "fabs.s  fa0, fa1"),
+        // Atomicas, fences y privilegiadas
+        (0x1405a52f, "lr.w.aq a0, (a1)"),
+        (0x0ec5a52f, "amoswap.w.aqrl a0, a2, (a1)"),
+        (0x0330000f, "fence   rw, rw"),
+        (0x0000100f, "fence.i"),
+        (0x30200073, "mret"),
+        (0x10500073, "wfi"),
+        (0x12000073, "sfence.vma"),
+        // Comprimidas, que se muestran expandidas
+        (0x00004515, "li      a0, 5"),
+        (0x0000a019, "j       pc + 6"),
+    ];
+
+    #[test]
+    fn disassemble_encodings() {
+        for (instr, expected) in CASES {
+            assert_eq!(disassemble(instr), expected, "{instr:#010x}");
+        }
+    }
+
+    #[test]
+    fn disassemble_compressed_uses_low_halfword() {
+        // Los 16 bits altos de una instruccion comprimida se ignoran
+        assert_eq!(disassemble(0xffff8082), "ret");
+        assert_eq!(disassemble(0x00004532), "lw      a0, 12(sp)");
+        assert_eq!(disassemble(0x00000808), "addi    a0, sp, 16");
+        assert_eq!(disassemble(0x00002522), "fld     fa0, 8(sp)");
+    }
+
+    #[test]
+    fn disassemble_invalid() {
+        assert_eq!(disassemble(0x00000000), "unimp");
+        assert_eq!(disassemble(0xffffffff), "unknown");
+    }
+}
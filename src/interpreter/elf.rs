@@ -0,0 +1,233 @@
+use std::{collections::HashMap, fmt};
+
+// Solo se admiten ELF de 32 bits, little endian y para RISC-V
+const ELF_MAGIC: [u8; 4] = [0x7F, b'E', b'L', b'F'];
+const ELFCLASS32: u8 = 1;
+const ELFDATA2LSB: u8 = 1;
+const EM_RISCV: u16 = 243;
+
+const PT_LOAD: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+
+const ELF_HEADER_SIZE: usize = 52;
+const PROGRAM_HEADER_SIZE: usize = 32;
+const SECTION_HEADER_SIZE: usize = 40;
+const SYMBOL_SIZE: usize = 16;
+
+const STT_SECTION: u8 = 3;
+const STT_FILE: u8 = 4;
+
+#[derive(Debug)]
+pub enum ElfError {
+    InvalidMagic,
+    UnsupportedClass,
+    UnsupportedEndianness,
+    UnsupportedMachine(u16),
+    Truncated,
+}
+
+impl fmt::Display for ElfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMagic => write!(f, "no es un fichero ELF"),
+            Self::UnsupportedClass => write!(f, "solo se admiten ELF de 32 bits"),
+            Self::UnsupportedEndianness => write!(f, "solo se admiten ELF little endian"),
+            Self::UnsupportedMachine(machine) => write!(f, "arquitectura no soportada ({machine})"),
+            Self::Truncated => write!(f, "fichero ELF truncado"),
+        }
+    }
+}
+
+pub struct Segment {
+    pub phys_address: u32,
+    // Contenido del fichero, el resto hasta mem_size (.bss) se rellena con ceros
+    pub data: Vec<u8>,
+    pub mem_size: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct Symbol {
+    pub name: String,
+    pub address: u32,
+    pub size: u32,
+}
+
+#[derive(Default)]
+pub struct SymbolTable {
+    // Ordenados por direccion
+    symbols: Vec<Symbol>,
+    by_name: HashMap<String, usize>,
+}
+
+impl SymbolTable {
+    pub fn new(mut symbols: Vec<Symbol>) -> Self {
+        symbols.sort_by_key(|symbol| symbol.address);
+
+        let mut by_name = HashMap::new();
+        for (i, symbol) in symbols.iter().enumerate() {
+            by_name.entry(symbol.name.clone()).or_insert(i);
+        }
+
+        Self { symbols, by_name }
+    }
+
+    pub fn get_address(&self, name: &str) -> Option<u32> {
+        self.by_name.get(name).map(|i| self.symbols[*i].address)
+    }
+
+    // Simbolo que contiene la direccion y desplazamiento dentro de el. Los
+    // simbolos sin tamano (etiquetas) cubren hasta el siguiente simbolo.
+    pub fn get_symbol(&self, address: u32) -> Option<(&Symbol, u32)> {
+        let index = self.symbols.partition_point(|symbol| symbol.address <= address);
+        let symbol = self.symbols[..index].last()?;
+        let offset = address - symbol.address;
+
+        if symbol.size != 0 && offset >= symbol.size {
+            return None;
+        }
+
+        Some((symbol, offset))
+    }
+}
+
+pub struct Elf {
+    pub entry: u32,
+    pub segments: Vec<Segment>,
+    pub symbols: SymbolTable,
+}
+
+impl Elf {
+    pub fn parse(bytes: &[u8]) -> Result<Self, ElfError> {
+        if bytes.len() < ELF_HEADER_SIZE {
+            return Err(ElfError::Truncated);
+        }
+
+        if bytes[0..4] != ELF_MAGIC {
+            return Err(ElfError::InvalidMagic);
+        }
+
+        if bytes[4] != ELFCLASS32 {
+            return Err(ElfError::UnsupportedClass);
+        }
+
+        if bytes[5] != ELFDATA2LSB {
+            return Err(ElfError::UnsupportedEndianness);
+        }
+
+        let machine = read_u16(bytes, 18)?;
+        if machine != EM_RISCV {
+            return Err(ElfError::UnsupportedMachine(machine));
+        }
+
+        let entry = read_u32(bytes, 24)?;
+
+        Ok(Self {
+            entry,
+            segments: parse_segments(bytes)?,
+            symbols: parse_symbols(bytes)?,
+        })
+    }
+}
+
+fn parse_segments(bytes: &[u8]) -> Result<Vec<Segment>, ElfError> {
+    let ph_offset = read_u32(bytes, 28)? as usize;
+    let ph_entry_size = read_u16(bytes, 42)? as usize;
+    let ph_num = read_u16(bytes, 44)? as usize;
+
+    let mut segments = Vec::new();
+
+    for i in 0..ph_num {
+        let header = get_slice(bytes, ph_offset + i * ph_entry_size, PROGRAM_HEADER_SIZE)?;
+
+        if read_u32(header, 0)? != PT_LOAD {
+            continue;
+        }
+
+        let offset = read_u32(header, 4)? as usize;
+        let phys_address = read_u32(header, 12)?;
+        let file_size = read_u32(header, 16)? as usize;
+        let mem_size = read_u32(header, 20)?;
+
+        segments.push(Segment {
+            phys_address,
+            data: get_slice(bytes, offset, file_size)?.to_vec(),
+            mem_size,
+        });
+    }
+
+    Ok(segments)
+}
+
+fn parse_symbols(bytes: &[u8]) -> Result<SymbolTable, ElfError> {
+    let sh_offset = read_u32(bytes, 32)? as usize;
+    let sh_entry_size = read_u16(bytes, 46)? as usize;
+    let sh_num = read_u16(bytes, 48)? as usize;
+
+    let get_section = |i: usize| get_slice(bytes, sh_offset + i * sh_entry_size, SECTION_HEADER_SIZE);
+
+    let mut symbols = Vec::new();
+
+    for i in 0..sh_num {
+        let header = get_section(i)?;
+
+        if read_u32(header, 4)? != SHT_SYMTAB {
+            continue;
+        }
+
+        let offset = read_u32(header, 16)? as usize;
+        let size = read_u32(header, 20)? as usize;
+
+        // sh_link apunta a la tabla de cadenas con los nombres
+        let strtab_header = get_section(read_u32(header, 24)? as usize)?;
+        let strtab_offset = read_u32(strtab_header, 16)? as usize;
+        let strtab_size = read_u32(strtab_header, 20)? as usize;
+        let strtab = get_slice(bytes, strtab_offset, strtab_size)?;
+
+        // La primera entrada siempre es nula
+        for entry in get_slice(bytes, offset, size)?.chunks_exact(SYMBOL_SIZE).skip(1) {
+            let symbol_type = entry[12] & 0xF;
+            let section_index = read_u16(entry, 14)?;
+
+            if symbol_type == STT_SECTION || symbol_type == STT_FILE || section_index == 0 {
+                continue;
+            }
+
+            let name = read_str(strtab, read_u32(entry, 0)? as usize);
+
+            if name.is_empty() {
+                continue;
+            }
+
+            symbols.push(Symbol {
+                name,
+                address: read_u32(entry, 4)?,
+                size: read_u32(entry, 8)?,
+            });
+        }
+    }
+
+    Ok(SymbolTable::new(symbols))
+}
+
+fn get_slice(bytes: &[u8], offset: usize, size: usize) -> Result<&[u8], ElfError> {
+    bytes.get(offset..offset.checked_add(size).ok_or(ElfError::Truncated)?).ok_or(ElfError::Truncated)
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, ElfError> {
+    let slice = get_slice(bytes, offset, 2)?;
+
+    Ok(u16::from_le_bytes([slice[0], slice[1]]))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, ElfError> {
+    let slice = get_slice(bytes, offset, 4)?;
+
+    Ok(u32::from_le_bytes([slice[0], slice[1], slice[2], slice[3]]))
+}
+
+fn read_str(strtab: &[u8], offset: usize) -> String {
+    let bytes = strtab.get(offset..).unwrap_or_default();
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
@@ -1,4 +1,7 @@
 pub mod rv32a;
+pub mod rv32c;
+pub mod rv32d;
+pub mod rv32f;
 pub mod rv32i;
 pub mod rv32m;
 pub mod rv32privileged;
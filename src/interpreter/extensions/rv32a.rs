@@ -1,11 +1,37 @@
 use crate::interpreter::{
     bus::Bus,
-    riscv_core::{AtomicInstruction, Exception, RVCore}, virtual_memory::sv32::{AccessType, translate_address},
+    csr::HpmEvent,
+    riscv_core::{AtomicInstruction, Exception, ExceptionType, RVCore},
+    virtual_memory::sv32::{AccessType, PhysicalAddress, translate_address_sized},
 };
 
+// Los accesos atomicos desalineados siempre provocan una excepcion, aunque el
+// bus emule el resto de accesos desalineados. Cuentan como evento Atomic en
+// cuanto se traduce la direccion
+fn translate_atomic(
+    core: &mut RVCore,
+    bus: &mut Bus,
+    address: u32,
+    access_type: AccessType,
+) -> Result<PhysicalAddress, Exception> {
+    if !address.is_multiple_of(4) {
+        let exception_type = match access_type {
+            AccessType::Load => ExceptionType::LoadAddressMisaligned,
+            _ => ExceptionType::StoreAmoAddressMisaligned,
+        };
+
+        return Err(Exception::new(exception_type, address));
+    }
+
+    let phys_address = translate_address_sized(core, bus, address, 4, access_type)?;
+    core.count_event(HpmEvent::Atomic);
+
+    Ok(phys_address)
+}
+
 pub fn lr_w(instr: &AtomicInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
     let address = core.read_reg(instr.rs1);
-    let phys_address = translate_address(core, bus, address, AccessType::Load)?;
+    let phys_address = translate_atomic(core, bus, address, AccessType::Load)?;
 
     let val = bus.read_aligned_word(&phys_address)?;
     core.write_reg(instr.rd, val);
@@ -16,7 +42,7 @@ pub fn lr_w(instr: &AtomicInstruction, bus: &mut Bus, core: &mut RVCore) -> Resu
 
 pub fn sc_w(instr: &AtomicInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
     let address = core.read_reg(instr.rs1);
-    let phys_address = translate_address(core, bus, address, AccessType::StoreAmo)?;
+    let phys_address = translate_atomic(core, bus, address, AccessType::StoreAmo)?;
 
     let rs2_val = core.read_reg(instr.rs2);
 
@@ -38,7 +64,7 @@ pub fn amoswap_w(
     core: &mut RVCore,
 ) -> Result<(), Exception> {
     let address = core.read_reg(instr.rs1);
-    let phys_address = translate_address(core, bus, address, AccessType::StoreAmo)?;
+    let phys_address = translate_atomic(core, bus, address, AccessType::StoreAmo)?;
 
     let tmp = bus.read_aligned_word(&phys_address)?;
 
@@ -55,7 +81,7 @@ pub fn amoadd_w(
     core: &mut RVCore,
 ) -> Result<(), Exception> {
     let address = core.read_reg(instr.rs1);
-    let phys_address = translate_address(core, bus, address, AccessType::StoreAmo)?;
+    let phys_address = translate_atomic(core, bus, address, AccessType::StoreAmo)?;
 
     let tmp = bus.read_aligned_word(&phys_address)?;
 
@@ -72,7 +98,7 @@ pub fn amoand_w(
     core: &mut RVCore,
 ) -> Result<(), Exception> {
     let address = core.read_reg(instr.rs1);
-    let phys_address = translate_address(core, bus, address, AccessType::StoreAmo)?;
+    let phys_address = translate_atomic(core, bus, address, AccessType::StoreAmo)?;
 
     let tmp = bus.read_aligned_word(&phys_address)?;
 
@@ -89,7 +115,7 @@ pub fn amoor_w(
     core: &mut RVCore,
 ) -> Result<(), Exception> {
     let address = core.read_reg(instr.rs1);
-    let phys_address = translate_address(core, bus, address, AccessType::StoreAmo)?;
+    let phys_address = translate_atomic(core, bus, address, AccessType::StoreAmo)?;
 
     let tmp = bus.read_aligned_word(&phys_address)?;
 
@@ -106,7 +132,7 @@ pub fn amoxor_w(
     core: &mut RVCore,
 ) -> Result<(), Exception> {
     let address = core.read_reg(instr.rs1);
-    let phys_address = translate_address(core, bus, address, AccessType::StoreAmo)?;
+    let phys_address = translate_atomic(core, bus, address, AccessType::StoreAmo)?;
 
     let tmp = bus.read_aligned_word(&phys_address)?;
 
@@ -123,7 +149,7 @@ pub fn amomax_w(
     core: &mut RVCore,
 ) -> Result<(), Exception> {
     let address = core.read_reg(instr.rs1);
-    let phys_address = translate_address(core, bus, address, AccessType::StoreAmo)?;
+    let phys_address = translate_atomic(core, bus, address, AccessType::StoreAmo)?;
 
     let tmp = bus.read_aligned_word(&phys_address)?;
 
@@ -143,7 +169,7 @@ pub fn amomin_w(
     core: &mut RVCore,
 ) -> Result<(), Exception> {
     let address = core.read_reg(instr.rs1);
-    let phys_address = translate_address(core, bus, address, AccessType::StoreAmo)?;
+    let phys_address = translate_atomic(core, bus, address, AccessType::StoreAmo)?;
 
     let tmp = bus.read_aligned_word(&phys_address)?;
 
@@ -163,7 +189,7 @@ pub fn amomaxu_w(
     core: &mut RVCore,
 ) -> Result<(), Exception> {
     let address = core.read_reg(instr.rs1);
-    let phys_address = translate_address(core, bus, address, AccessType::StoreAmo)?;
+    let phys_address = translate_atomic(core, bus, address, AccessType::StoreAmo)?;
 
     let tmp = bus.read_aligned_word(&phys_address)?;
 
@@ -180,7 +206,7 @@ pub fn amominu_w(
     core: &mut RVCore,
 ) -> Result<(), Exception> {
     let address = core.read_reg(instr.rs1);
-    let phys_address = translate_address(core, bus, address, AccessType::StoreAmo)?;
+    let phys_address = translate_atomic(core, bus, address, AccessType::StoreAmo)?;
 
     let tmp = bus.read_aligned_word(&phys_address)?;
 
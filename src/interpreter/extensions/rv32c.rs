@@ -0,0 +1,296 @@
+// Las instrucciones comprimidas no tienen semantica propia: cada una se expande
+// a su equivalente de 32 bits y se decodifica con el decodificador normal.
+
+const OPCODE_LOAD: u32 = 0b0000011;
+const OPCODE_LOAD_FP: u32 = 0b0000111;
+const OPCODE_OP_IMM: u32 = 0b0010011;
+const OPCODE_STORE: u32 = 0b0100011;
+const OPCODE_STORE_FP: u32 = 0b0100111;
+const OPCODE_OP: u32 = 0b0110011;
+const OPCODE_LUI: u32 = 0b0110111;
+const OPCODE_BRANCH: u32 = 0b1100011;
+const OPCODE_JALR: u32 = 0b1100111;
+const OPCODE_JAL: u32 = 0b1101111;
+const OPCODE_SYSTEM: u32 = 0b1110011;
+
+const REG_RA: u32 = 1;
+const REG_SP: u32 = 2;
+
+pub fn is_compressed(instr: u32) -> bool {
+    instr & 0b11 != 0b11
+}
+
+pub fn expand_compressed(instr: u16) -> Option<u32> {
+    let instr = instr as u32;
+
+    let quadrant = instr & 0b11;
+    let funct3 = (instr >> 13) & 0x7;
+
+    match quadrant {
+        0b00 => expand_quadrant_0(instr, funct3),
+        0b01 => expand_quadrant_1(instr, funct3),
+        0b10 => expand_quadrant_2(instr, funct3),
+        _ => None,
+    }
+}
+
+fn expand_quadrant_0(instr: u32, funct3: u32) -> Option<u32> {
+    let rd_prime = get_reg_prime(instr >> 2);
+    let rs1_prime = get_reg_prime(instr >> 7);
+
+    match funct3 {
+        // C.ADDI4SPN
+        0b000 => {
+            let nzuimm = (((instr >> 11) & 0x3) << 4)
+                | (((instr >> 7) & 0xF) << 6)
+                | (((instr >> 6) & 0x1) << 2)
+                | (((instr >> 5) & 0x1) << 3);
+
+            if nzuimm == 0 {
+                return None;
+            }
+
+            Some(encode_i(nzuimm, REG_SP, 0x0, rd_prime, OPCODE_OP_IMM))
+        }
+        // C.FLD
+        0b001 => Some(encode_i(get_cl_double_offset(instr), rs1_prime, 0x3, rd_prime, OPCODE_LOAD_FP)),
+        // C.LW
+        0b010 => Some(encode_i(get_cl_word_offset(instr), rs1_prime, 0x2, rd_prime, OPCODE_LOAD)),
+        // C.FLW
+        0b011 => Some(encode_i(get_cl_word_offset(instr), rs1_prime, 0x2, rd_prime, OPCODE_LOAD_FP)),
+        // C.FSD
+        0b101 => Some(encode_s(get_cl_double_offset(instr), rs1_prime, rd_prime, 0x3, OPCODE_STORE_FP)),
+        // C.SW
+        0b110 => Some(encode_s(get_cl_word_offset(instr), rs1_prime, rd_prime, 0x2, OPCODE_STORE)),
+        // C.FSW
+        0b111 => Some(encode_s(get_cl_word_offset(instr), rs1_prime, rd_prime, 0x2, OPCODE_STORE_FP)),
+
+        _ => None,
+    }
+}
+
+fn expand_quadrant_1(instr: u32, funct3: u32) -> Option<u32> {
+    let rd = (instr >> 7) & 0x1F;
+    let rd_prime = get_reg_prime(instr >> 7);
+    let rs2_prime = get_reg_prime(instr >> 2);
+
+    match funct3 {
+        // C.ADDI / C.NOP
+        0b000 => Some(encode_i(get_ci_imm(instr), rd, 0x0, rd, OPCODE_OP_IMM)),
+        // C.JAL
+        0b001 => Some(encode_j(get_cj_imm(instr), REG_RA)),
+        // C.LI
+        0b010 => Some(encode_i(get_ci_imm(instr), 0, 0x0, rd, OPCODE_OP_IMM)),
+        0b011 => {
+            if rd == REG_SP {
+                // C.ADDI16SP
+                let nzimm = sign_extend(
+                    (((instr >> 12) & 0x1) << 9)
+                        | (((instr >> 6) & 0x1) << 4)
+                        | (((instr >> 5) & 0x1) << 6)
+                        | (((instr >> 3) & 0x3) << 7)
+                        | (((instr >> 2) & 0x1) << 5),
+                    10,
+                );
+
+                if nzimm == 0 {
+                    return None;
+                }
+
+                Some(encode_i(nzimm, REG_SP, 0x0, REG_SP, OPCODE_OP_IMM))
+            } else {
+                // C.LUI
+                let nzimm = get_ci_imm(instr);
+
+                if nzimm == 0 {
+                    return None;
+                }
+
+                Some((nzimm << 12) | (rd << 7) | OPCODE_LUI)
+            }
+        }
+        0b100 => {
+            let funct2 = (instr >> 10) & 0x3;
+            let shamt_5 = (instr >> 12) & 0x1;
+            let shamt = (instr >> 2) & 0x1F;
+
+            match funct2 {
+                // C.SRLI (shamt[5] debe ser 0 en RV32)
+                0b00 if shamt_5 == 0 => Some(encode_i(shamt, rd_prime, 0x5, rd_prime, OPCODE_OP_IMM)),
+                // C.SRAI
+                0b01 if shamt_5 == 0 => Some(encode_i(
+                    (0x20 << 5) | shamt,
+                    rd_prime,
+                    0x5,
+                    rd_prime,
+                    OPCODE_OP_IMM,
+                )),
+                // C.ANDI
+                0b10 => Some(encode_i(get_ci_imm(instr), rd_prime, 0x7, rd_prime, OPCODE_OP_IMM)),
+                0b11 if shamt_5 == 0 => {
+                    let (funct3, funct7) = match (instr >> 5) & 0x3 {
+                        // C.SUB
+                        0b00 => (0x0, 0x20),
+                        // C.XOR
+                        0b01 => (0x4, 0x00),
+                        // C.OR
+                        0b10 => (0x6, 0x00),
+                        // C.AND
+                        _ => (0x7, 0x00),
+                    };
+
+                    Some(encode_r(funct7, rs2_prime, rd_prime, funct3, rd_prime, OPCODE_OP))
+                }
+
+                _ => None,
+            }
+        }
+        // C.J
+        0b101 => Some(encode_j(get_cj_imm(instr), 0)),
+        // C.BEQZ
+        0b110 => Some(encode_b(get_cb_imm(instr), rd_prime, 0, 0x0)),
+        // C.BNEZ
+        0b111 => Some(encode_b(get_cb_imm(instr), rd_prime, 0, 0x1)),
+
+        _ => None,
+    }
+}
+
+fn expand_quadrant_2(instr: u32, funct3: u32) -> Option<u32> {
+    let rd = (instr >> 7) & 0x1F;
+    let rs2 = (instr >> 2) & 0x1F;
+    let bit_12 = (instr >> 12) & 0x1;
+
+    match funct3 {
+        // C.SLLI (shamt[5] debe ser 0 en RV32)
+        0b000 if bit_12 == 0 => Some(encode_i(rs2, rd, 0x1, rd, OPCODE_OP_IMM)),
+        // C.FLDSP
+        0b001 => {
+            let uimm = (bit_12 << 5) | (((instr >> 5) & 0x3) << 3) | (((instr >> 2) & 0x7) << 6);
+
+            Some(encode_i(uimm, REG_SP, 0x3, rd, OPCODE_LOAD_FP))
+        }
+        // C.LWSP
+        0b010 => {
+            if rd == 0 {
+                return None;
+            }
+
+            Some(encode_i(get_ci_word_sp_offset(instr), REG_SP, 0x2, rd, OPCODE_LOAD))
+        }
+        // C.FLWSP
+        0b011 => Some(encode_i(get_ci_word_sp_offset(instr), REG_SP, 0x2, rd, OPCODE_LOAD_FP)),
+        0b100 => match (bit_12, rd, rs2) {
+            // C.JR
+            (0, 0, 0) => None,
+            (0, _, 0) => Some(encode_i(0, rd, 0x0, 0, OPCODE_JALR)),
+            // C.MV
+            (0, _, _) => Some(encode_r(0x00, rs2, 0, 0x0, rd, OPCODE_OP)),
+            // C.EBREAK
+            (1, 0, 0) => Some(encode_i(1, 0, 0x0, 0, OPCODE_SYSTEM)),
+            // C.JALR
+            (1, _, 0) => Some(encode_i(0, rd, 0x0, REG_RA, OPCODE_JALR)),
+            // C.ADD
+            _ => Some(encode_r(0x00, rs2, rd, 0x0, rd, OPCODE_OP)),
+        },
+        // C.FSDSP
+        0b101 => {
+            let uimm = (((instr >> 10) & 0x7) << 3) | (((instr >> 7) & 0x7) << 6);
+
+            Some(encode_s(uimm, REG_SP, rs2, 0x3, OPCODE_STORE_FP))
+        }
+        // C.SWSP
+        0b110 => Some(encode_s(get_css_word_sp_offset(instr), REG_SP, rs2, 0x2, OPCODE_STORE)),
+        // C.FSWSP
+        0b111 => Some(encode_s(get_css_word_sp_offset(instr), REG_SP, rs2, 0x2, OPCODE_STORE_FP)),
+
+        _ => None,
+    }
+}
+
+// Registros x8-x15 de los formatos CIW, CL, CS, CA y CB
+fn get_reg_prime(bits: u32) -> u32 {
+    (bits & 0x7) + 8
+}
+
+fn sign_extend(val: u32, bits: u32) -> u32 {
+    let shift = 32 - bits;
+    (((val << shift) as i32) >> shift) as u32
+}
+
+fn get_ci_imm(instr: u32) -> u32 {
+    sign_extend((((instr >> 12) & 0x1) << 5) | ((instr >> 2) & 0x1F), 6)
+}
+
+fn get_cl_word_offset(instr: u32) -> u32 {
+    (((instr >> 10) & 0x7) << 3) | (((instr >> 6) & 0x1) << 2) | (((instr >> 5) & 0x1) << 6)
+}
+
+fn get_cl_double_offset(instr: u32) -> u32 {
+    (((instr >> 10) & 0x7) << 3) | (((instr >> 5) & 0x3) << 6)
+}
+
+fn get_ci_word_sp_offset(instr: u32) -> u32 {
+    (((instr >> 12) & 0x1) << 5) | (((instr >> 4) & 0x7) << 2) | (((instr >> 2) & 0x3) << 6)
+}
+
+fn get_css_word_sp_offset(instr: u32) -> u32 {
+    (((instr >> 9) & 0xF) << 2) | (((instr >> 7) & 0x3) << 6)
+}
+
+fn get_cj_imm(instr: u32) -> u32 {
+    sign_extend(
+        (((instr >> 12) & 0x1) << 11)
+            | (((instr >> 11) & 0x1) << 4)
+            | (((instr >> 9) & 0x3) << 8)
+            | (((instr >> 8) & 0x1) << 10)
+            | (((instr >> 7) & 0x1) << 6)
+            | (((instr >> 6) & 0x1) << 7)
+            | (((instr >> 3) & 0x7) << 1)
+            | (((instr >> 2) & 0x1) << 5),
+        12,
+    )
+}
+
+fn get_cb_imm(instr: u32) -> u32 {
+    sign_extend(
+        (((instr >> 12) & 0x1) << 8)
+            | (((instr >> 10) & 0x3) << 3)
+            | (((instr >> 5) & 0x3) << 6)
+            | (((instr >> 3) & 0x3) << 1)
+            | (((instr >> 2) & 0x1) << 5),
+        9,
+    )
+}
+
+fn encode_r(funct7: u32, rs2: u32, rs1: u32, funct3: u32, rd: u32, opcode: u32) -> u32 {
+    (funct7 << 25) | (rs2 << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | opcode
+}
+
+fn encode_i(imm: u32, rs1: u32, funct3: u32, rd: u32, opcode: u32) -> u32 {
+    ((imm & 0xFFF) << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | opcode
+}
+
+fn encode_s(imm: u32, rs1: u32, rs2: u32, funct3: u32, opcode: u32) -> u32 {
+    (((imm >> 5) & 0x7F) << 25) | (rs2 << 20) | (rs1 << 15) | (funct3 << 12) | ((imm & 0x1F) << 7) | opcode
+}
+
+fn encode_b(imm: u32, rs1: u32, rs2: u32, funct3: u32) -> u32 {
+    (((imm >> 12) & 0x1) << 31)
+        | (((imm >> 5) & 0x3F) << 25)
+        | (rs2 << 20)
+        | (rs1 << 15)
+        | (funct3 << 12)
+        | (((imm >> 1) & 0xF) << 8)
+        | (((imm >> 11) & 0x1) << 7)
+        | OPCODE_BRANCH
+}
+
+fn encode_j(imm: u32, rd: u32) -> u32 {
+    (((imm >> 20) & 0x1) << 31)
+        | (((imm >> 1) & 0x3FF) << 21)
+        | (((imm >> 11) & 0x1) << 20)
+        | (((imm >> 12) & 0xFF) << 12)
+        | (rd << 7)
+        | OPCODE_JAL
+}
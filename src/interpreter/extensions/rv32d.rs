@@ -0,0 +1,125 @@
+use crate::interpreter::{
+    bus::Bus,
+    extensions::rv32f::*,
+    riscv_core::{Exception, FInstruction, RVCore},
+    softfloat::{self, FloatFormat},
+    virtual_memory::access::{load, store},
+};
+
+pub fn fld(instr: &FInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    check_fs(instr, core)?;
+
+    let rs1_val = core.read_reg(instr.rs1);
+    let address = rs1_val.wrapping_add(instr.imm);
+
+    let val = load(core, bus, address, 8)?;
+    write_result(core, instr.rd, FloatFormat::Double, val);
+
+    Ok(())
+}
+
+pub fn fsd(instr: &FInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    check_fs(instr, core)?;
+
+    let rs1_val = core.read_reg(instr.rs1);
+    let address = rs1_val.wrapping_add(instr.imm);
+
+    let val = core.read_freg(instr.rs2);
+    store(core, bus, address, 8, val)
+}
+
+pub fn fadd_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    arith_op(instr, core, FloatFormat::Double, softfloat::add)
+}
+
+pub fn fsub_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    arith_op(instr, core, FloatFormat::Double, softfloat::sub)
+}
+
+pub fn fmul_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    arith_op(instr, core, FloatFormat::Double, softfloat::mul)
+}
+
+pub fn fdiv_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    arith_op(instr, core, FloatFormat::Double, softfloat::div)
+}
+
+pub fn fsqrt_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    sqrt_op(instr, core, FloatFormat::Double)
+}
+
+pub fn fmadd_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    fused_op(instr, core, FloatFormat::Double, false, false)
+}
+
+pub fn fmsub_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    fused_op(instr, core, FloatFormat::Double, false, true)
+}
+
+pub fn fnmsub_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    fused_op(instr, core, FloatFormat::Double, true, false)
+}
+
+pub fn fnmadd_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    fused_op(instr, core, FloatFormat::Double, true, true)
+}
+
+pub fn fsgnj_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    sign_inject_op(instr, core, FloatFormat::Double, |_, sign_b| sign_b)
+}
+
+pub fn fsgnjn_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    sign_inject_op(instr, core, FloatFormat::Double, |_, sign_b| !sign_b)
+}
+
+pub fn fsgnjx_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    sign_inject_op(instr, core, FloatFormat::Double, |sign_a, sign_b| sign_a ^ sign_b)
+}
+
+pub fn fmin_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    min_max_op(instr, core, FloatFormat::Double, softfloat::min)
+}
+
+pub fn fmax_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    min_max_op(instr, core, FloatFormat::Double, softfloat::max)
+}
+
+pub fn feq_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    compare_op(instr, core, FloatFormat::Double, softfloat::eq)
+}
+
+pub fn flt_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    compare_op(instr, core, FloatFormat::Double, softfloat::lt)
+}
+
+pub fn fle_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    compare_op(instr, core, FloatFormat::Double, softfloat::le)
+}
+
+pub fn fclass_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    classify_op(instr, core, FloatFormat::Double)
+}
+
+pub fn fcvt_w_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    to_int_op(instr, core, FloatFormat::Double, true)
+}
+
+pub fn fcvt_wu_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    to_int_op(instr, core, FloatFormat::Double, false)
+}
+
+pub fn fcvt_d_w(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    from_int_op(instr, core, FloatFormat::Double, true)
+}
+
+pub fn fcvt_d_wu(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    from_int_op(instr, core, FloatFormat::Double, false)
+}
+
+pub fn fcvt_s_d(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    convert_op(instr, core, FloatFormat::Double, FloatFormat::Single)
+}
+
+pub fn fcvt_d_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    convert_op(instr, core, FloatFormat::Single, FloatFormat::Double)
+}
@@ -0,0 +1,341 @@
+use crate::interpreter::{
+    bus::Bus,
+    riscv_core::{Exception, ExceptionType, FInstruction, RVCore},
+    softfloat::{self, FloatFormat, RoundingMode},
+    virtual_memory::access::{load, store},
+};
+
+const NAN_BOX: u64 = 0xFFFFFFFF_00000000;
+
+// Con mstatus.FS a Off cualquier instruccion de coma flotante es ilegal
+pub fn check_fs(instr: &FInstruction, core: &RVCore) -> Result<(), Exception> {
+    if core.control_and_status.is_fs_off() {
+        return Err(Exception::new(ExceptionType::IllegalInstruction, instr.data));
+    }
+
+    Ok(())
+}
+
+// rm = 7 usa el modo dinamico de frm, los valores reservados son ilegales
+pub fn get_rounding_mode(instr: &FInstruction, core: &RVCore) -> Result<RoundingMode, Exception> {
+    let rm = if instr.rm == 0b111 {
+        core.control_and_status.read_frm()
+    } else {
+        instr.rm
+    };
+
+    RoundingMode::new(rm).ok_or(Exception::new(ExceptionType::IllegalInstruction, instr.data))
+}
+
+pub fn read_operand(core: &RVCore, reg: u32, fmt: FloatFormat) -> u64 {
+    let val = core.read_freg(reg);
+
+    match fmt {
+        // Un valor simple mal encapsulado se lee como el NaN canonico
+        FloatFormat::Single if val & NAN_BOX != NAN_BOX => fmt.canonical_nan(),
+        FloatFormat::Single => val & 0xFFFFFFFF,
+        FloatFormat::Double => val,
+    }
+}
+
+pub fn write_result(core: &mut RVCore, reg: u32, fmt: FloatFormat, val: u64) {
+    match fmt {
+        FloatFormat::Single => core.write_freg(reg, NAN_BOX | val),
+        FloatFormat::Double => core.write_freg(reg, val),
+    }
+}
+
+fn accrue_flags(core: &mut RVCore, flags: u32) {
+    if flags != 0 {
+        core.control_and_status.accrue_fflags(flags);
+    }
+}
+
+pub fn arith_op(
+    instr: &FInstruction,
+    core: &mut RVCore,
+    fmt: FloatFormat,
+    op: fn(FloatFormat, u64, u64, RoundingMode) -> (u64, u32),
+) -> Result<(), Exception> {
+    check_fs(instr, core)?;
+    let rm = get_rounding_mode(instr, core)?;
+
+    let rs1_val = read_operand(core, instr.rs1, fmt);
+    let rs2_val = read_operand(core, instr.rs2, fmt);
+
+    let (val, flags) = op(fmt, rs1_val, rs2_val, rm);
+    write_result(core, instr.rd, fmt, val);
+    accrue_flags(core, flags);
+
+    Ok(())
+}
+
+pub fn sqrt_op(instr: &FInstruction, core: &mut RVCore, fmt: FloatFormat) -> Result<(), Exception> {
+    check_fs(instr, core)?;
+    let rm = get_rounding_mode(instr, core)?;
+
+    let rs1_val = read_operand(core, instr.rs1, fmt);
+
+    let (val, flags) = softfloat::sqrt(fmt, rs1_val, rm);
+    write_result(core, instr.rd, fmt, val);
+    accrue_flags(core, flags);
+
+    Ok(())
+}
+
+// (-1)^negate_product * rs1 * rs2 + (-1)^negate_addend * rs3
+pub fn fused_op(
+    instr: &FInstruction,
+    core: &mut RVCore,
+    fmt: FloatFormat,
+    negate_product: bool,
+    negate_addend: bool,
+) -> Result<(), Exception> {
+    check_fs(instr, core)?;
+    let rm = get_rounding_mode(instr, core)?;
+
+    let sign_bit = fmt.sign_mask();
+    let rs1_val = read_operand(core, instr.rs1, fmt) ^ if negate_product { sign_bit } else { 0 };
+    let rs2_val = read_operand(core, instr.rs2, fmt);
+    let rs3_val = read_operand(core, instr.rs3, fmt) ^ if negate_addend { sign_bit } else { 0 };
+
+    let (val, flags) = softfloat::fused_mul_add(fmt, rs1_val, rs2_val, rs3_val, rm);
+    write_result(core, instr.rd, fmt, val);
+    accrue_flags(core, flags);
+
+    Ok(())
+}
+
+// El signo del resultado se calcula a partir de los signos de rs1 y rs2
+pub fn sign_inject_op(
+    instr: &FInstruction,
+    core: &mut RVCore,
+    fmt: FloatFormat,
+    sign_fn: fn(bool, bool) -> bool,
+) -> Result<(), Exception> {
+    check_fs(instr, core)?;
+
+    let sign_bit = fmt.sign_mask();
+    let rs1_val = read_operand(core, instr.rs1, fmt);
+    let rs2_val = read_operand(core, instr.rs2, fmt);
+
+    let sign = sign_fn(rs1_val & sign_bit != 0, rs2_val & sign_bit != 0);
+    let val = (rs1_val & !sign_bit) | if sign { sign_bit } else { 0 };
+    write_result(core, instr.rd, fmt, val);
+
+    Ok(())
+}
+
+pub fn min_max_op(
+    instr: &FInstruction,
+    core: &mut RVCore,
+    fmt: FloatFormat,
+    op: fn(FloatFormat, u64, u64) -> (u64, u32),
+) -> Result<(), Exception> {
+    check_fs(instr, core)?;
+
+    let rs1_val = read_operand(core, instr.rs1, fmt);
+    let rs2_val = read_operand(core, instr.rs2, fmt);
+
+    let (val, flags) = op(fmt, rs1_val, rs2_val);
+    write_result(core, instr.rd, fmt, val);
+    accrue_flags(core, flags);
+
+    Ok(())
+}
+
+pub fn compare_op(
+    instr: &FInstruction,
+    core: &mut RVCore,
+    fmt: FloatFormat,
+    op: fn(FloatFormat, u64, u64) -> (bool, u32),
+) -> Result<(), Exception> {
+    check_fs(instr, core)?;
+
+    let rs1_val = read_operand(core, instr.rs1, fmt);
+    let rs2_val = read_operand(core, instr.rs2, fmt);
+
+    let (val, flags) = op(fmt, rs1_val, rs2_val);
+    core.write_reg(instr.rd, val as u32);
+    accrue_flags(core, flags);
+
+    Ok(())
+}
+
+pub fn classify_op(instr: &FInstruction, core: &mut RVCore, fmt: FloatFormat) -> Result<(), Exception> {
+    check_fs(instr, core)?;
+
+    let rs1_val = read_operand(core, instr.rs1, fmt);
+    core.write_reg(instr.rd, softfloat::classify(fmt, rs1_val));
+
+    Ok(())
+}
+
+pub fn to_int_op(instr: &FInstruction, core: &mut RVCore, fmt: FloatFormat, signed: bool) -> Result<(), Exception> {
+    check_fs(instr, core)?;
+    let rm = get_rounding_mode(instr, core)?;
+
+    let rs1_val = read_operand(core, instr.rs1, fmt);
+
+    let (val, flags) = softfloat::to_int(fmt, rs1_val, rm, signed);
+    core.write_reg(instr.rd, val);
+    accrue_flags(core, flags);
+
+    Ok(())
+}
+
+pub fn from_int_op(instr: &FInstruction, core: &mut RVCore, fmt: FloatFormat, signed: bool) -> Result<(), Exception> {
+    check_fs(instr, core)?;
+    let rm = get_rounding_mode(instr, core)?;
+
+    let rs1_val = core.read_reg(instr.rs1);
+
+    let (val, flags) = softfloat::from_int(fmt, rs1_val, rm, signed);
+    write_result(core, instr.rd, fmt, val);
+    accrue_flags(core, flags);
+
+    Ok(())
+}
+
+pub fn convert_op(instr: &FInstruction, core: &mut RVCore, from: FloatFormat, to: FloatFormat) -> Result<(), Exception> {
+    check_fs(instr, core)?;
+    let rm = get_rounding_mode(instr, core)?;
+
+    let rs1_val = read_operand(core, instr.rs1, from);
+
+    let (val, flags) = softfloat::convert(from, to, rs1_val, rm);
+    write_result(core, instr.rd, to, val);
+    accrue_flags(core, flags);
+
+    Ok(())
+}
+
+pub fn flw(instr: &FInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    check_fs(instr, core)?;
+
+    let rs1_val = core.read_reg(instr.rs1);
+    let address = rs1_val.wrapping_add(instr.imm);
+
+    let val = load(core, bus, address, 4)?;
+    write_result(core, instr.rd, FloatFormat::Single, val);
+
+    Ok(())
+}
+
+pub fn fsw(instr: &FInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    check_fs(instr, core)?;
+
+    let rs1_val = core.read_reg(instr.rs1);
+    let address = rs1_val.wrapping_add(instr.imm);
+
+    // Se guardan los 32 bits bajos sin comprobar el NaN-boxing
+    let val = core.read_freg(instr.rs2) as u32;
+    store(core, bus, address, 4, val as u64)
+}
+
+pub fn fadd_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    arith_op(instr, core, FloatFormat::Single, softfloat::add)
+}
+
+pub fn fsub_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    arith_op(instr, core, FloatFormat::Single, softfloat::sub)
+}
+
+pub fn fmul_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    arith_op(instr, core, FloatFormat::Single, softfloat::mul)
+}
+
+pub fn fdiv_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    arith_op(instr, core, FloatFormat::Single, softfloat::div)
+}
+
+pub fn fsqrt_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    sqrt_op(instr, core, FloatFormat::Single)
+}
+
+pub fn fmadd_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    fused_op(instr, core, FloatFormat::Single, false, false)
+}
+
+pub fn fmsub_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    fused_op(instr, core, FloatFormat::Single, false, true)
+}
+
+pub fn fnmsub_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    fused_op(instr, core, FloatFormat::Single, true, false)
+}
+
+pub fn fnmadd_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    fused_op(instr, core, FloatFormat::Single, true, true)
+}
+
+pub fn fsgnj_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    sign_inject_op(instr, core, FloatFormat::Single, |_, sign_b| sign_b)
+}
+
+pub fn fsgnjn_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    sign_inject_op(instr, core, FloatFormat::Single, |_, sign_b| !sign_b)
+}
+
+pub fn fsgnjx_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    sign_inject_op(instr, core, FloatFormat::Single, |sign_a, sign_b| sign_a ^ sign_b)
+}
+
+pub fn fmin_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    min_max_op(instr, core, FloatFormat::Single, softfloat::min)
+}
+
+pub fn fmax_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    min_max_op(instr, core, FloatFormat::Single, softfloat::max)
+}
+
+pub fn feq_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    compare_op(instr, core, FloatFormat::Single, softfloat::eq)
+}
+
+pub fn flt_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    compare_op(instr, core, FloatFormat::Single, softfloat::lt)
+}
+
+pub fn fle_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    compare_op(instr, core, FloatFormat::Single, softfloat::le)
+}
+
+pub fn fclass_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    classify_op(instr, core, FloatFormat::Single)
+}
+
+pub fn fcvt_w_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    to_int_op(instr, core, FloatFormat::Single, true)
+}
+
+pub fn fcvt_wu_s(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    to_int_op(instr, core, FloatFormat::Single, false)
+}
+
+pub fn fcvt_s_w(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    from_int_op(instr, core, FloatFormat::Single, true)
+}
+
+pub fn fcvt_s_wu(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    from_int_op(instr, core, FloatFormat::Single, false)
+}
+
+pub fn fmv_x_w(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    check_fs(instr, core)?;
+
+    // Se mueven los 32 bits bajos tal cual, sin comprobar el NaN-boxing
+    let val = core.read_freg(instr.rs1) as u32;
+    core.write_reg(instr.rd, val);
+
+    Ok(())
+}
+
+pub fn fmv_w_x(instr: &FInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    check_fs(instr, core)?;
+
+    let val = core.read_reg(instr.rs1);
+    write_result(core, instr.rd, FloatFormat::Single, val as u64);
+
+    Ok(())
+}
@@ -1,8 +1,9 @@
 use crate::interpreter::{
     bus::Bus,
+    csr::HpmEvent,
     riscv_core::{
-        BInstruction, Exception, ExceptionType, IInstruction, JInstruction, RInstruction, RVCore, SInstruction, UInstruction, WithVal
-    }, virtual_memory::sv32::{AccessType, translate_address},
+        BInstruction, Exception, ExceptionType, IInstruction, JInstruction, RInstruction, RVCore, SInstruction, UInstruction
+    }, virtual_memory::access::{load, store},
 };
 
 pub fn add(instr: &RInstruction, core: &mut RVCore) -> Result<(), Exception> {
@@ -165,12 +166,8 @@ pub fn sltui(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(),
 pub fn lb(instr: &IInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
     let rs1_val = core.read_reg(instr.rs1);
     let address = rs1_val.wrapping_add(instr.imm);
-    let phys_address = translate_address(core, bus, address, AccessType::Load)?;
 
-    let mem_val = bus.read_byte(&phys_address).with_val(address)?;
-    let extend_val = (mem_val & 0b10000000 > 0) as u8 * 0xFF;
-
-    let val = u32::from_le_bytes([mem_val, extend_val, extend_val, extend_val]);
+    let val = load(core, bus, address, 1)? as u8 as i8 as i32 as u32;
     core.write_reg(instr.rd, val);
 
     Ok(())
@@ -179,14 +176,8 @@ pub fn lb(instr: &IInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(),
 pub fn lh(instr: &IInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
     let rs1_val = core.read_reg(instr.rs1);
     let address = rs1_val.wrapping_add(instr.imm);
-    let phys_address = translate_address(core, bus, address, AccessType::Load)?;
-
-    let val_1 = bus.read_byte(&phys_address).with_val(address)?;
-    let val_2 = bus.read_byte(&phys_address.wrapping_add(1)).with_val(address)?;
-
-    let extend_val = (val_2 & 0b10000000 > 0) as u8 * 0xFF;
 
-    let val = u32::from_le_bytes([val_1, val_2, extend_val, extend_val]);
+    let val = load(core, bus, address, 2)? as u16 as i16 as i32 as u32;
     core.write_reg(instr.rd, val);
 
     Ok(())
@@ -195,10 +186,8 @@ pub fn lh(instr: &IInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(),
 pub fn lw(instr: &IInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
     let rs1_val = core.read_reg(instr.rs1);
     let address = rs1_val.wrapping_add(instr.imm);
-    let phys_address = translate_address(core, bus, address, AccessType::Load)?;
-
-    let val = bus.read_aligned_word(&phys_address).with_val(address)?;
 
+    let val = load(core, bus, address, 4)? as u32;
     core.write_reg(instr.rd, val);
 
     Ok(())
@@ -207,9 +196,8 @@ pub fn lw(instr: &IInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(),
 pub fn lbu(instr: &IInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
     let rs1_val = core.read_reg(instr.rs1);
     let address = rs1_val.wrapping_add(instr.imm);
-    let phys_address = translate_address(core, bus, address, AccessType::Load)?;
 
-    let val = bus.read_byte(&phys_address).with_val(address)? as u32;
+    let val = load(core, bus, address, 1)? as u32;
     core.write_reg(instr.rd, val);
 
     Ok(())
@@ -218,13 +206,10 @@ pub fn lbu(instr: &IInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(),
 pub fn lhu(instr: &IInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
     let rs1_val = core.read_reg(instr.rs1);
     let address = rs1_val.wrapping_add(instr.imm);
-    let phys_address = translate_address(core, bus, address, AccessType::Load)?;
-
-    let val_1 = bus.read_byte(&phys_address).with_val(address)?;
-    let val_2 = bus.read_byte(&phys_address.wrapping_add(1)).with_val(address)?;
 
-    let val = u32::from_le_bytes([val_1, val_2, 0x00, 0x00]);
+    let val = load(core, bus, address, 2)? as u32;
     core.write_reg(instr.rd, val);
+
     Ok(())
 }
 
@@ -232,28 +217,24 @@ pub fn sb(instr: &SInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(),
     let rs1_val = core.read_reg(instr.rs1);
     let rs2_val = core.read_reg(instr.rs2);
     let address = rs1_val.wrapping_add(instr.imm);
-    let phys_address = translate_address(core, bus, address, AccessType::StoreAmo)?;
 
-    bus.write_byte(&phys_address, rs2_val as u8).with_val(address)
+    store(core, bus, address, 1, rs2_val as u64)
 }
 
 pub fn sh(instr: &SInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
     let rs1_val = core.read_reg(instr.rs1);
     let rs2_val = core.read_reg(instr.rs2);
     let address = rs1_val.wrapping_add(instr.imm);
-    let phys_address = translate_address(core, bus, address, AccessType::StoreAmo)?;
 
-    bus.write_byte(&phys_address, rs2_val as u8).with_val(address)?;
-    bus.write_byte(&phys_address.wrapping_add(1), (rs2_val >> 8) as u8).with_val(address)
+    store(core, bus, address, 2, rs2_val as u64)
 }
 
 pub fn sw(instr: &SInstruction, bus: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
     let rs1_val = core.read_reg(instr.rs1);
     let rs2_val = core.read_reg(instr.rs2);
     let address = rs1_val.wrapping_add(instr.imm);
-    let phys_address = translate_address(core, bus, address, AccessType::StoreAmo)?;
 
-    bus.write_aligned_word(&phys_address, rs2_val).with_val(address)
+    store(core, bus, address, 4, rs2_val as u64)
 }
 
 pub fn beq(instr: &BInstruction, core: &mut RVCore) -> Result<(), Exception> {
@@ -262,11 +243,12 @@ pub fn beq(instr: &BInstruction, core: &mut RVCore) -> Result<(), Exception> {
     if rs1_val == rs2_val {
         let new_pc = core.pc.wrapping_add(instr.imm);
 
-        if new_pc % 4 != 0 {
+        if !new_pc.is_multiple_of(core.get_ialign()) {
             return Err(Exception::new(ExceptionType::InstructionAddressMisaligned, new_pc));
         }
 
-        core.pc = new_pc.wrapping_sub(4);
+        core.next_pc = new_pc;
+        core.count_event(HpmEvent::TakenBranch);
     }
 
     Ok(())
@@ -278,11 +260,12 @@ pub fn bne(instr: &BInstruction, core: &mut RVCore) -> Result<(), Exception> {
     if rs1_val != rs2_val {
         let new_pc = core.pc.wrapping_add(instr.imm);
 
-        if new_pc % 4 != 0 {
+        if !new_pc.is_multiple_of(core.get_ialign()) {
             return Err(Exception::new(ExceptionType::InstructionAddressMisaligned, new_pc));
         }
 
-        core.pc = new_pc.wrapping_sub(4);
+        core.next_pc = new_pc;
+        core.count_event(HpmEvent::TakenBranch);
     }
 
     Ok(())
@@ -294,11 +277,12 @@ pub fn blt(instr: &BInstruction, core: &mut RVCore) -> Result<(), Exception> {
     if (rs1_val as i32) < (rs2_val as i32) {
         let new_pc = core.pc.wrapping_add(instr.imm);
 
-        if new_pc % 4 != 0 {
+        if !new_pc.is_multiple_of(core.get_ialign()) {
             return Err(Exception::new(ExceptionType::InstructionAddressMisaligned, new_pc));
         }
 
-        core.pc = new_pc.wrapping_sub(4);
+        core.next_pc = new_pc;
+        core.count_event(HpmEvent::TakenBranch);
     }
 
     Ok(())
@@ -310,11 +294,12 @@ pub fn bge(instr: &BInstruction, core: &mut RVCore) -> Result<(), Exception> {
     if (rs1_val as i32) >= (rs2_val as i32) {
         let new_pc = core.pc.wrapping_add(instr.imm);
 
-        if new_pc % 4 != 0 {
+        if !new_pc.is_multiple_of(core.get_ialign()) {
             return Err(Exception::new(ExceptionType::InstructionAddressMisaligned, new_pc));
         }
 
-        core.pc = new_pc.wrapping_sub(4);
+        core.next_pc = new_pc;
+        core.count_event(HpmEvent::TakenBranch);
     }
 
     Ok(())
@@ -326,11 +311,12 @@ pub fn bltu(instr: &BInstruction, core: &mut RVCore) -> Result<(), Exception> {
     if rs1_val < rs2_val {
         let new_pc = core.pc.wrapping_add(instr.imm);
 
-        if new_pc % 4 != 0 {
+        if !new_pc.is_multiple_of(core.get_ialign()) {
             return Err(Exception::new(ExceptionType::InstructionAddressMisaligned, new_pc));
         }
 
-        core.pc = new_pc.wrapping_sub(4);
+        core.next_pc = new_pc;
+        core.count_event(HpmEvent::TakenBranch);
     }
 
     Ok(())
@@ -342,11 +328,12 @@ pub fn bgeu(instr: &BInstruction, core: &mut RVCore) -> Result<(), Exception> {
     if rs1_val >= rs2_val {
         let new_pc = core.pc.wrapping_add(instr.imm);
 
-        if new_pc % 4 != 0 {
+        if !new_pc.is_multiple_of(core.get_ialign()) {
             return Err(Exception::new(ExceptionType::InstructionAddressMisaligned, new_pc));
         }
 
-        core.pc = new_pc.wrapping_sub(4);
+        core.next_pc = new_pc;
+        core.count_event(HpmEvent::TakenBranch);
     }
 
     Ok(())
@@ -354,12 +341,12 @@ pub fn bgeu(instr: &BInstruction, core: &mut RVCore) -> Result<(), Exception> {
 
 pub fn jal(instr: &JInstruction, core: &mut RVCore) -> Result<(), Exception> {
     let new_pc = core.pc.wrapping_add(instr.imm);
-    if new_pc % 4 != 0 {
+    if !new_pc.is_multiple_of(core.get_ialign()) {
         return Err(Exception::new(ExceptionType::InstructionAddressMisaligned, new_pc));
     }
 
-    core.write_reg(instr.rd, core.pc.wrapping_add(4));
-    core.pc = new_pc.wrapping_sub(4);
+    core.write_reg(instr.rd, core.next_pc);
+    core.next_pc = new_pc;
 
     Ok(())
 }
@@ -367,12 +354,12 @@ pub fn jal(instr: &JInstruction, core: &mut RVCore) -> Result<(), Exception> {
 pub fn jalr(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
     let rs1_val = core.read_reg(instr.rs1);
     let new_pc = rs1_val.wrapping_add(instr.imm) & 0xFFFFFFFE;
-    if new_pc % 4 != 0 {
+    if !new_pc.is_multiple_of(core.get_ialign()) {
         return Err(Exception::new(ExceptionType::InstructionAddressMisaligned, new_pc));
     }
 
-    core.write_reg(instr.rd, core.pc.wrapping_add(4));
-    core.pc = new_pc.wrapping_sub(4);
+    core.write_reg(instr.rd, core.next_pc);
+    core.next_pc = new_pc;
 
     Ok(())
 }
@@ -1,5 +1,5 @@
 use crate::interpreter::{
-    bus::Bus, csr::{MEPC, MSTATUS, SEPC, SSTATUS}, riscv_core::{Exception, ExceptionType, IInstruction, PrivilegeLevel, RVCore}
+    bus::Bus, csr::{MEPC, MSTATUS, SATP_ASID_MASK, SEPC, SSTATUS}, riscv_core::{Exception, ExceptionType, IInstruction, PrivilegeLevel, RVCore}
 };
 
 pub fn ecall(_: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
@@ -15,7 +15,7 @@ pub fn ebreak(_: &IInstruction, _: &mut Bus, _: &mut RVCore) -> Result<(), Excep
 }
 
 pub fn mret(_: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
-    core.pc = core.control_and_status.read_csr(MEPC, core.privilege_level)?.wrapping_sub(4);
+    core.next_pc = core.control_and_status.read_csr(MEPC, core.privilege_level)?;
 
     let mut mstatus = core
         .control_and_status
@@ -41,10 +41,10 @@ pub fn mret(_: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exce
 
 // TODO SRET
 pub fn sret(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
-    core.pc = core.control_and_status.read_csr(SEPC, core.privilege_level)?.wrapping_sub(4);
+    core.next_pc = core.control_and_status.read_csr(SEPC, core.privilege_level)?;
 
     let mut sstatus = core.control_and_status.read_sstatus(core.privilege_level)?;
-    let mut mstatus = core
+    let mstatus = core
         .control_and_status
         .read_mstatus_unchecked();
 
@@ -60,25 +60,57 @@ pub fn sret(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(),
     sstatus.set_sie(spie);
     sstatus.set_spie(true);
     sstatus.set_spp(false);
-    
+    core.control_and_status.write_csr(SSTATUS, core.privilege_level, sstatus.0)?;
+
+    // mstatus se vuelve a leer para no pisar los cambios de sstatus
     if spp_y != PrivilegeLevel::Machine {
+        let mut mstatus = core.control_and_status.read_mstatus_unchecked();
         mstatus.set_mprv(false);
+        // Puede que un poco hacky
+        core.control_and_status.write_csr(MSTATUS, PrivilegeLevel::Machine, mstatus.0).unwrap();
     }
-    core.control_and_status.write_csr(SSTATUS, core.privilege_level, sstatus.0)?;
-    // Puede que un poco hacky
-    core.control_and_status.write_csr(MSTATUS, PrivilegeLevel::Machine, mstatus.0).unwrap();
 
     core.privilege_level = spp_y;
 
     Ok(())
 }
 
-pub fn sfence_vma(instr: &IInstruction, _: &mut Bus, core: &mut RVCore)  -> Result<(), Exception> { 
-    let mstatus = core.control_and_status.read_mstatus(core.privilege_level).expect("Puede que esto no vaya asi");
+// Con mstatus.TW activo wfi solo se puede ejecutar en modo maquina, y en modo
+// usuario nunca. El interprete deja de ejecutar el hart hasta que tenga una
+// interrupcion pendiente y habilitada en mie, aunque mstatus.MIE/SIE esten a 0
+pub fn wfi(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    let tw = core.control_and_status.read_mstatus_unchecked().get_tw();
 
-    if mstatus.get_tvm() {
-        return Err(Exception::new(ExceptionType::IllegalInstruction, instr.data));
+    match core.privilege_level {
+        PrivilegeLevel::Machine => {}
+        PrivilegeLevel::Supervisor if !tw => {}
+        _ => return Err(Exception::new(ExceptionType::IllegalInstruction, instr.data)),
     }
 
+    core.waiting_for_interrupt = true;
+
+    Ok(())
+}
+
+// rs1 indica la direccion virtual y rs2 el ASID de las traducciones que se
+// eliminan de la TLB. Con x0 se eliminan las de todas las direcciones/ASIDs
+pub fn sfence_vma(instr: &IInstruction, _: &mut Bus, core: &mut RVCore)  -> Result<(), Exception> {
+    let mstatus = core.control_and_status.read_mstatus_unchecked();
+
+    match core.privilege_level {
+        PrivilegeLevel::User => return Err(Exception::new(ExceptionType::IllegalInstruction, instr.data)),
+        PrivilegeLevel::Supervisor if mstatus.get_tvm() => {
+            return Err(Exception::new(ExceptionType::IllegalInstruction, instr.data));
+        }
+        _ => {}
+    }
+
+    let rs2 = instr.imm & 0x1F;
+
+    let vpn = (instr.rs1 != 0).then(|| core.read_reg(instr.rs1) >> 12);
+    let asid = (rs2 != 0).then(|| core.read_reg(rs2) & SATP_ASID_MASK);
+
+    core.tlb.flush(vpn, asid);
+
     Ok(())
 }
\ No newline at end of file
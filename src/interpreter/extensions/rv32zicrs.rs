@@ -1,115 +1,126 @@
-use crate::interpreter::{
-    bus::Bus,
-    riscv_core::{Exception, IInstruction, RVCore, WithVal},
-};
-
-pub fn csrrw(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
-    let csr = instr.imm as usize;
-    let rs1_val = core.read_reg(instr.rs1);
-    
-    if instr.rd != 0 {
-        let old_csr = core
-            .control_and_status
-            .read_csr(csr, core.privilege_level)
-            .with_val(instr.data)?;
-        core.write_reg(instr.rd, old_csr);
-    }
-
-    core.control_and_status
-        .write_csr(csr, core.privilege_level, rs1_val)?;
-
-    Ok(())
-}
-
-pub fn csrrs(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
-    let csr = instr.imm as usize;
-    let old_csr = core
-        .control_and_status
-        .read_csr(csr, core.privilege_level)
-        .with_val(instr.data)?;
-
-    if instr.rs1 != 0 {
-        let rs1_val = core.read_reg(instr.rs1);
-        let new_csr = old_csr | rs1_val;
-        core.control_and_status
-            .write_csr(csr, core.privilege_level, new_csr)?;
-    }
-    core.write_reg(instr.rd, old_csr);
-
-    Ok(())
-}
-
-pub fn csrrc(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
-    let csr = instr.imm as usize;
-    let rs1_val = core.read_reg(instr.rs1);
-    
-    let old_csr = core
-        .control_and_status
-        .read_csr(csr, core.privilege_level)
-        .with_val(instr.data)?;
-
-    if instr.rs1 != 0 {
-        let new_csr = old_csr & !rs1_val;
-        core.control_and_status
-            .write_csr(csr, core.privilege_level, new_csr)?;
-    }
-    core.write_reg(instr.rd, old_csr);
-
-    Ok(())
-}
-
-pub fn csrrwi(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
-    let csr = instr.imm as usize;
-    let imm_val = instr.rs1;
-
-    if instr.rd != 0 {
-        let old_csr = core
-            .control_and_status
-            .read_csr(csr, core.privilege_level)
-            .with_val(instr.data)?;
-        core.write_reg(instr.rd, old_csr);
-    }
-
-    core.control_and_status
-        .write_csr(csr, core.privilege_level, imm_val)?;
-
-    Ok(())
-}
-
-pub fn csrrsi(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
-    let csr = instr.imm as usize;
-    let imm_val = instr.rs1;
-
-    let old_csr = core
-        .control_and_status
-        .read_csr(csr, core.privilege_level)
-        .with_val(instr.data)?;
-    core.write_reg(instr.rd, old_csr);
-
-    if imm_val != 0 {
-        let new_csr = old_csr | imm_val;
-        core.control_and_status
-            .write_csr(csr, core.privilege_level, new_csr)?;
-    }
-
-    Ok(())
-}
-
-pub fn csrrci(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
-    let csr = instr.imm as usize;
-    let imm_val = instr.rs1;
-
-    let old_csr = core
-        .control_and_status
-        .read_csr(csr, core.privilege_level)
-        .with_val(instr.data)?;
-    core.write_reg(instr.rd, old_csr);
-
-    if imm_val != 0 {
-        let new_csr = old_csr & !imm_val;
-        core.control_and_status
-            .write_csr(csr, core.privilege_level, new_csr)?;
-    }
-
-    Ok(())
-}
+use crate::interpreter::{
+    bus::Bus,
+    csr::{SATP, is_pmp_csr},
+    riscv_core::{Exception, IInstruction, RVCore, WithVal},
+};
+
+// Los bloques decodificados se han comprobado con la configuracion de PMP
+// anterior, asi que se descartan al cambiarla. Al cambiar satp se vacia la TLB
+fn write_csr(core: &mut RVCore, csr: usize, val: u32) -> Result<(), Exception> {
+    core.control_and_status.write_csr(csr, core.privilege_level, val)?;
+
+    if is_pmp_csr(csr) {
+        core.flush_block_cache();
+    }
+
+    if csr == SATP {
+        core.tlb.flush_non_global();
+    }
+
+    Ok(())
+}
+
+pub fn csrrw(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    let csr = instr.imm as usize;
+    let rs1_val = core.read_reg(instr.rs1);
+    
+    if instr.rd != 0 {
+        let old_csr = core
+            .control_and_status
+            .read_csr(csr, core.privilege_level)
+            .with_val(instr.data)?;
+        core.write_reg(instr.rd, old_csr);
+    }
+
+    write_csr(core, csr, rs1_val)?;
+
+    Ok(())
+}
+
+pub fn csrrs(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    let csr = instr.imm as usize;
+    let old_csr = core
+        .control_and_status
+        .read_csr(csr, core.privilege_level)
+        .with_val(instr.data)?;
+
+    if instr.rs1 != 0 {
+        let rs1_val = core.read_reg(instr.rs1);
+        let new_csr = old_csr | rs1_val;
+        write_csr(core, csr, new_csr)?;
+    }
+    core.write_reg(instr.rd, old_csr);
+
+    Ok(())
+}
+
+pub fn csrrc(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    let csr = instr.imm as usize;
+    let rs1_val = core.read_reg(instr.rs1);
+    
+    let old_csr = core
+        .control_and_status
+        .read_csr(csr, core.privilege_level)
+        .with_val(instr.data)?;
+
+    if instr.rs1 != 0 {
+        let new_csr = old_csr & !rs1_val;
+        write_csr(core, csr, new_csr)?;
+    }
+    core.write_reg(instr.rd, old_csr);
+
+    Ok(())
+}
+
+pub fn csrrwi(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    let csr = instr.imm as usize;
+    let imm_val = instr.rs1;
+
+    if instr.rd != 0 {
+        let old_csr = core
+            .control_and_status
+            .read_csr(csr, core.privilege_level)
+            .with_val(instr.data)?;
+        core.write_reg(instr.rd, old_csr);
+    }
+
+    write_csr(core, csr, imm_val)?;
+
+    Ok(())
+}
+
+pub fn csrrsi(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    let csr = instr.imm as usize;
+    let imm_val = instr.rs1;
+
+    let old_csr = core
+        .control_and_status
+        .read_csr(csr, core.privilege_level)
+        .with_val(instr.data)?;
+    core.write_reg(instr.rd, old_csr);
+
+    if imm_val != 0 {
+        let new_csr = old_csr | imm_val;
+        write_csr(core, csr, new_csr)?;
+    }
+
+    Ok(())
+}
+
+pub fn csrrci(instr: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    let csr = instr.imm as usize;
+    let imm_val = instr.rs1;
+
+    let old_csr = core
+        .control_and_status
+        .read_csr(csr, core.privilege_level)
+        .with_val(instr.data)?;
+    core.write_reg(instr.rd, old_csr);
+
+    if imm_val != 0 {
+        let new_csr = old_csr & !imm_val;
+        write_csr(core, csr, new_csr)?;
+    }
+
+    Ok(())
+}
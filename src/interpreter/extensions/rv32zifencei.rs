@@ -1,6 +1,9 @@
 use crate::interpreter::{bus::Bus, riscv_core::{Exception, IInstruction, RVCore}};
 
-pub fn fence_i(_: &IInstruction, _: &mut Bus, _: &mut RVCore) -> Result<(), Exception> {
-    // TODO
+// Descarta las instrucciones decodificadas del hart, asi que a partir de aqui
+// ve todas las escrituras en memoria, incluidas las de otros harts. fence.i
+// termina el bloque, asi que la siguiente instruccion ya se decodifica de nuevo
+pub fn fence_i(_: &IInstruction, _: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    core.flush_block_cache();
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,770 @@
+// Servidor del protocolo remoto de GDB (RSP) sobre TCP. Para conectarse:
+//
+//   riscv32-unknown-elf-gdb programa.elf -ex "target remote localhost:1234"
+//
+// Numeracion de los registros (la misma que usa GDB para RISC-V):
+//
+// 0-31     x0-x31
+// 32       pc
+// 33-64    f0-f31 (64 bits)
+// 65+csr   CSRs
+//
+// Comandos de `monitor`:
+//
+// disas [direccion [n]]   desensambla n instrucciones (10 por defecto) desde
+//                         direccion (pc por defecto)
+
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+};
+
+use crate::{
+    interpreter::{
+        ExitStatus, Interpreter,
+        csr::{CSR_NAMES, FCSR, FFLAGS, FRM},
+        disassembler::{F_REG_NAMES, X_REG_NAMES},
+        extensions::rv32c::is_compressed,
+        riscv_core::PrivilegeLevel,
+        virtual_memory::sv32::debug_translate,
+    },
+    utils::parse_number,
+};
+
+const REG_PC: usize = 32;
+const REG_F0: usize = 33;
+const REG_CSR0: usize = 65;
+const REG_END: usize = REG_CSR0 + 4096;
+
+// Tamano maximo de los paquetes que se anuncia en qSupported
+const PACKET_SIZE: usize = 0x4000;
+
+// Instrucciones que se ejecutan entre comprobaciones de Ctrl-C al continuar
+const CONTINUE_CHUNK: u64 = 10000;
+
+const DISAS_DEFAULT_COUNT: u32 = 10;
+
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+const SIGSEGV: u8 = 11;
+
+const INTERRUPT: u8 = 0x03;
+
+// Espera a que se conecte GDB y atiende sus peticiones. Devuelve el estado con
+// el que ha terminado el programa o None si GDB se ha desconectado antes.
+pub fn serve(interpreter: &mut Interpreter, address: impl ToSocketAddrs) -> io::Result<Option<ExitStatus>> {
+    let listener = TcpListener::bind(address)?;
+    let (stream, _) = listener.accept()?;
+
+    GdbStub::new(interpreter, stream).run()
+}
+
+struct GdbStub<'a> {
+    interpreter: &'a mut Interpreter,
+    stream: TcpStream,
+    no_ack: bool,
+}
+
+impl<'a> GdbStub<'a> {
+    fn new(interpreter: &'a mut Interpreter, stream: TcpStream) -> Self {
+        Self {
+            interpreter,
+            stream,
+            no_ack: false,
+        }
+    }
+
+    fn run(&mut self) -> io::Result<Option<ExitStatus>> {
+        self.stream.set_nodelay(true)?;
+
+        while let Some(packet) = self.read_packet()? {
+            let status = match packet.as_bytes().first() {
+                Some(b'c') => self.resume(&packet[1..], false)?,
+                Some(b's') => self.resume(&packet[1..], true)?,
+                Some(b'v') if packet.starts_with("vCont;") => self.resume_vcont(&packet[6..])?,
+                Some(b'D') => {
+                    self.send_packet("OK")?;
+                    return Ok(None);
+                }
+                Some(b'k') => return Ok(None),
+                _ => {
+                    let response = self.handle_packet(&packet);
+                    self.send_packet(&response)?;
+                    None
+                }
+            };
+
+            // Cuando el programa termina GDB cierra la sesion
+            if let Some(status @ ExitStatus::Halted { .. }) = status {
+                return Ok(Some(status));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn handle_packet(&mut self, packet: &str) -> String {
+        let Some(command) = packet.get(..1) else {
+            return String::new();
+        };
+        let args = &packet[1..];
+
+        match command {
+            "?" => format!("S{SIGTRAP:02x}"),
+            "g" => self.read_registers(),
+            "G" => self.write_registers(args),
+            "p" => self.read_register(args),
+            "P" => self.write_register(args),
+            "m" => self.read_memory(args),
+            "M" => self.write_memory(args),
+            "Z" | "z" => self.set_breakpoint(args, command == "Z"),
+            "H" | "T" => "OK".to_string(),
+            "v" if args == "Cont?" => "vCont;c;C;s;S".to_string(),
+            "q" | "Q" => self.handle_query(packet),
+            _ => String::new(),
+        }
+    }
+
+    fn handle_query(&mut self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            return format!("PacketSize={PACKET_SIZE:x};QStartNoAckMode+;qXfer:features:read+;swbreak+");
+        }
+
+        if let Some(range) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            return read_xfer(&target_xml(), range);
+        }
+
+        if let Some(command) = packet.strip_prefix("qRcmd,") {
+            return self.monitor_command(command);
+        }
+
+        match packet {
+            "QStartNoAckMode" => {
+                self.no_ack = true;
+                "OK".to_string()
+            }
+            "qAttached" => "1".to_string(),
+            "qC" => "QC1".to_string(),
+            "qfThreadInfo" => "m1".to_string(),
+            "qsThreadInfo" => "l".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    // La respuesta de monitor es el texto que muestra GDB codificado en hexadecimal
+    fn monitor_command(&mut self, command: &str) -> String {
+        let Some(command) = decode_hex(command).and_then(|bytes| String::from_utf8(bytes).ok()) else {
+            return "E01".to_string();
+        };
+
+        let mut args = command.split_whitespace();
+
+        let output = match args.next() {
+            Some("disas") => {
+                let address = args.next().map_or(Some(self.interpreter.core().pc), parse_number);
+                let count = args.next().map_or(Some(DISAS_DEFAULT_COUNT), parse_number);
+
+                match (address, count) {
+                    (Some(address), Some(count)) => self.disassemble(address, count),
+                    _ => "Uso: disas [direccion [n]]\n".to_string(),
+                }
+            }
+            _ => format!("Comando desconocido: {command}\n"),
+        };
+
+        encode_hex(output.as_bytes())
+    }
+
+    fn disassemble(&mut self, mut address: u32, count: u32) -> String {
+        let mut output = String::new();
+
+        for _ in 0..count {
+            match self.interpreter.disassemble_at(address) {
+                Ok((instr, text)) if is_compressed(instr) => {
+                    output += &format!("0x{address:08x}:  {:04x}      {text}\n", instr & 0xFFFF);
+                    address = address.wrapping_add(2);
+                }
+                Ok((instr, text)) => {
+                    output += &format!("0x{address:08x}:  {instr:08x}  {text}\n");
+                    address = address.wrapping_add(4);
+                }
+                Err(_) => {
+                    output += &format!("0x{address:08x}:  no se puede leer la memoria\n");
+                    break;
+                }
+            }
+        }
+
+        output
+    }
+
+    // c y s pueden llevar la direccion en la que continuar. Devuelve el estado
+    // en el que se ha parado el programa
+    fn resume(&mut self, address: &str, single_step: bool) -> io::Result<Option<ExitStatus>> {
+        if !address.is_empty() {
+            match u32::from_str_radix(address, 16) {
+                Ok(address) => self.interpreter.core_mut().pc = address,
+                Err(_) => {
+                    self.send_packet("E01")?;
+                    return Ok(None);
+                }
+            }
+        }
+
+        let status = if single_step {
+            self.interpreter.step_instruction()
+        } else {
+            self.continue_execution()?
+        };
+
+        let reply = match status {
+            ExitStatus::Halted { exit_code } => format!("W{:02x}", exit_code & 0xFF),
+            ExitStatus::Breakpoint { .. } => format!("T{SIGTRAP:02x}swbreak:;"),
+            ExitStatus::InstructionLimit if single_step => format!("S{SIGTRAP:02x}"),
+            // Al continuar solo se para antes de tiempo si GDB manda Ctrl-C
+            ExitStatus::InstructionLimit => format!("S{SIGINT:02x}"),
+            ExitStatus::FatalError { .. } => format!("S{SIGSEGV:02x}"),
+        };
+
+        self.send_packet(&reply)?;
+
+        Ok(Some(status))
+    }
+
+    // vCont;<accion>[:hilo];... Solo se muestra un hilo a GDB, asi que se usa la
+    // primera accion. Las senales de C y S se ignoran
+    fn resume_vcont(&mut self, actions: &str) -> io::Result<Option<ExitStatus>> {
+        match actions.as_bytes().first() {
+            Some(b'c' | b'C') => self.resume("", false),
+            Some(b's' | b'S') => self.resume("", true),
+            _ => {
+                self.send_packet("E01")?;
+                Ok(None)
+            }
+        }
+    }
+
+    // Se ejecuta por bloques para poder atender el Ctrl-C de GDB. La primera
+    // instruccion se ejecuta aunque haya un breakpoint en ella
+    fn continue_execution(&mut self) -> io::Result<ExitStatus> {
+        let mut status = self.interpreter.step_instruction();
+
+        while status == ExitStatus::InstructionLimit {
+            if self.poll_interrupt()? {
+                break;
+            }
+
+            status = self.interpreter.run_for(CONTINUE_CHUNK);
+        }
+
+        Ok(status)
+    }
+
+    fn poll_interrupt(&mut self) -> io::Result<bool> {
+        let mut buf = [0u8];
+
+        self.stream.set_nonblocking(true)?;
+        let result = self.stream.read(&mut buf);
+        self.stream.set_nonblocking(false)?;
+
+        match result {
+            Ok(0) => Err(ErrorKind::UnexpectedEof.into()),
+            Ok(_) => Ok(buf[0] == INTERRUPT),
+            Err(err) if err.kind() == ErrorKind::WouldBlock => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn read_registers(&self) -> String {
+        (0..=REG_PC).filter_map(|reg| self.get_register(reg)).collect()
+    }
+
+    fn write_registers(&mut self, args: &str) -> String {
+        if args.len() < (REG_PC + 1) * 8 {
+            return "E01".to_string();
+        }
+
+        for reg in 0..=REG_PC {
+            let val = &args[reg * 8..(reg + 1) * 8];
+
+            if !self.set_register(reg, val) {
+                return "E01".to_string();
+            }
+        }
+
+        "OK".to_string()
+    }
+
+    fn read_register(&self, args: &str) -> String {
+        usize::from_str_radix(args, 16)
+            .ok()
+            .and_then(|reg| self.get_register(reg))
+            .unwrap_or_else(|| "E01".to_string())
+    }
+
+    fn write_register(&mut self, args: &str) -> String {
+        let Some((reg, val)) = args.split_once('=') else {
+            return "E01".to_string();
+        };
+
+        match usize::from_str_radix(reg, 16) {
+            Ok(reg) if self.set_register(reg, val) => "OK".to_string(),
+            _ => "E01".to_string(),
+        }
+    }
+
+    fn get_register(&self, reg: usize) -> Option<String> {
+        let core = self.interpreter.core();
+
+        match reg {
+            0..REG_PC => Some(encode_hex(&core.read_reg(reg as u32).to_le_bytes())),
+            REG_PC => Some(encode_hex(&core.pc.to_le_bytes())),
+            REG_F0..REG_CSR0 => Some(encode_hex(&core.read_freg((reg - REG_F0) as u32).to_le_bytes())),
+            REG_CSR0..REG_END => core
+                .control_and_status
+                .read_csr(reg - REG_CSR0, PrivilegeLevel::Machine)
+                .ok()
+                .map(|val| encode_hex(&val.to_le_bytes())),
+            _ => None,
+        }
+    }
+
+    fn set_register(&mut self, reg: usize, val: &str) -> bool {
+        let Some(bytes) = decode_hex(val) else {
+            return false;
+        };
+
+        let core = self.interpreter.core_mut();
+
+        match (reg, bytes.len()) {
+            (0..REG_PC, 4) => core.write_reg(reg as u32, u32::from_le_bytes(bytes.try_into().unwrap())),
+            (REG_PC, 4) => core.pc = u32::from_le_bytes(bytes.try_into().unwrap()),
+            (REG_F0..REG_CSR0, 8) => core.write_freg((reg - REG_F0) as u32, u64::from_le_bytes(bytes.try_into().unwrap())),
+            (REG_CSR0..REG_END, 4) => {
+                return core
+                    .control_and_status
+                    .write_csr(reg - REG_CSR0, PrivilegeLevel::Machine, u32::from_le_bytes(bytes.try_into().unwrap()))
+                    .is_ok();
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    // Las direcciones son virtuales y se traducen como lo haria el hart, pero
+    // sin efectos sobre el estado de la maquina ni comprobaciones de PMP
+    fn read_memory(&mut self, args: &str) -> String {
+        let Some((address, length)) = parse_address_length(args) else {
+            return "E01".to_string();
+        };
+
+        // La respuesta tiene que caber en un paquete. GDB pide el resto despues
+        let length = length.min((PACKET_SIZE / 2) as u32);
+        let mut bytes = Vec::with_capacity(length as usize);
+
+        for i in 0..length {
+            let address = address.wrapping_add(i);
+            let interpreter = &mut *self.interpreter;
+
+            let byte = debug_translate(&interpreter.cores[interpreter.current_hart], &mut interpreter.bus, address)
+                .and_then(|phys_address| interpreter.bus.read_byte(&phys_address).ok());
+
+            match byte {
+                Some(byte) => bytes.push(byte),
+                // Si no se ha podido leer nada se devuelve un error
+                None if bytes.is_empty() => return "E14".to_string(),
+                None => break,
+            }
+        }
+
+        encode_hex(&bytes)
+    }
+
+    fn write_memory(&mut self, args: &str) -> String {
+        let Some((range, data)) = args.split_once(':') else {
+            return "E01".to_string();
+        };
+
+        let (Some((address, length)), Some(bytes)) = (parse_address_length(range), decode_hex(data)) else {
+            return "E01".to_string();
+        };
+
+        if bytes.len() != length as usize {
+            return "E01".to_string();
+        }
+
+        for (i, byte) in bytes.into_iter().enumerate() {
+            let address = address.wrapping_add(i as u32);
+            let interpreter = &mut *self.interpreter;
+
+            let written = debug_translate(&interpreter.cores[interpreter.current_hart], &mut interpreter.bus, address)
+                .is_some_and(|phys_address| interpreter.bus.write_byte(&phys_address, byte).is_ok());
+
+            if !written {
+                return "E14".to_string();
+            }
+        }
+
+        "OK".to_string()
+    }
+
+    // Los breakpoints hardware (Z1) se tratan igual que los software (Z0)
+    fn set_breakpoint(&mut self, args: &str, insert: bool) -> String {
+        let mut fields = args.split(',');
+
+        let (Some("0" | "1"), Some(address)) = (fields.next(), fields.next()) else {
+            return String::new();
+        };
+
+        let Ok(address) = u32::from_str_radix(address, 16) else {
+            return "E01".to_string();
+        };
+
+        if insert {
+            self.interpreter.add_breakpoint(address);
+        } else {
+            self.interpreter.remove_breakpoint(address);
+        }
+
+        "OK".to_string()
+    }
+
+    // Paquetes: $<datos>#<checksum>. Devuelve None si GDB ha cerrado la conexion
+    fn read_packet(&mut self) -> io::Result<Option<String>> {
+        loop {
+            let mut byte = [0u8];
+
+            // Se descarta todo hasta el inicio del paquete (acks, Ctrl-C tardios...)
+            loop {
+                if self.stream.read(&mut byte)? == 0 {
+                    return Ok(None);
+                }
+
+                if byte[0] == b'$' {
+                    break;
+                }
+            }
+
+            let mut data = Vec::new();
+
+            loop {
+                if self.stream.read(&mut byte)? == 0 {
+                    return Ok(None);
+                }
+
+                if byte[0] == b'#' {
+                    break;
+                }
+
+                data.push(byte[0]);
+            }
+
+            let mut checksum = [0u8; 2];
+            self.stream.read_exact(&mut checksum)?;
+
+            let expected = std::str::from_utf8(&checksum).ok().and_then(|checksum| u8::from_str_radix(checksum, 16).ok());
+
+            if self.no_ack {
+                return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+            }
+
+            if expected == Some(compute_checksum(&data)) {
+                self.stream.write_all(b"+")?;
+                return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+            }
+
+            self.stream.write_all(b"-")?;
+        }
+    }
+
+    fn send_packet(&mut self, data: &str) -> io::Result<()> {
+        let packet = format!("${data}#{:02x}", compute_checksum(data.as_bytes()));
+
+        loop {
+            self.stream.write_all(packet.as_bytes())?;
+
+            if self.no_ack {
+                return Ok(());
+            }
+
+            // Se reenvia hasta que GDB lo confirma
+            let mut ack = [0u8];
+            loop {
+                if self.stream.read(&mut ack)? == 0 {
+                    return Err(ErrorKind::UnexpectedEof.into());
+                }
+
+                if ack[0] == b'+' || ack[0] == b'-' {
+                    break;
+                }
+            }
+
+            if ack[0] == b'+' {
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn target_xml() -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\"?><!DOCTYPE target SYSTEM \"gdb-target.dtd\"><target version=\"1.0\">\
+         <architecture>riscv:rv32</architecture><feature name=\"org.gnu.gdb.riscv.cpu\">",
+    );
+
+    for (i, name) in X_REG_NAMES.iter().enumerate() {
+        let reg_type = match *name {
+            "sp" => "data_ptr",
+            "ra" => "code_ptr",
+            _ => "int",
+        };
+
+        xml += &format!("<reg name=\"{name}\" bitsize=\"32\" regnum=\"{i}\" type=\"{reg_type}\"/>");
+    }
+
+    xml += &format!("<reg name=\"pc\" bitsize=\"32\" regnum=\"{REG_PC}\" type=\"code_ptr\"/></feature>");
+    xml += "<feature name=\"org.gnu.gdb.riscv.fpu\">";
+
+    for (i, name) in F_REG_NAMES.iter().enumerate() {
+        xml += &format!("<reg name=\"{name}\" bitsize=\"64\" regnum=\"{}\" type=\"ieee_double\"/>", REG_F0 + i);
+    }
+
+    for (name, csr) in CSR_NAMES.iter().filter(|(_, csr)| is_fp_csr(*csr)) {
+        xml += &format!("<reg name=\"{name}\" bitsize=\"32\" regnum=\"{}\" type=\"int\"/>", REG_CSR0 + csr);
+    }
+
+    xml += "</feature><feature name=\"org.gnu.gdb.riscv.csr\">";
+
+    // Con p/P se puede acceder a cualquier CSR aunque no este en la lista
+    for (name, csr) in CSR_NAMES.iter().filter(|(_, csr)| !is_fp_csr(*csr)) {
+        xml += &format!("<reg name=\"{name}\" bitsize=\"32\" regnum=\"{}\" type=\"int\"/>", REG_CSR0 + csr);
+    }
+
+    xml += "</feature></target>";
+
+    xml
+}
+
+fn is_fp_csr(csr: usize) -> bool {
+    matches!(csr, FFLAGS | FRM | FCSR)
+}
+
+// qXfer:<objeto>:read:<anexo>:<offset>,<longitud>
+fn read_xfer(data: &str, range: &str) -> String {
+    let Some((offset, length)) = range
+        .split_once(',')
+        .and_then(|(offset, length)| Some((usize::from_str_radix(offset, 16).ok()?, usize::from_str_radix(length, 16).ok()?)))
+    else {
+        return "E01".to_string();
+    };
+
+    let end = offset.saturating_add(length);
+    let chunk = data.get(offset.min(data.len())..end.min(data.len())).unwrap_or_default();
+
+    if end >= data.len() {
+        format!("l{chunk}")
+    } else {
+        format!("m{chunk}")
+    }
+}
+
+fn parse_address_length(args: &str) -> Option<(u32, u32)> {
+    let (address, length) = args.split_once(',')?;
+
+    Some((u32::from_str_radix(address, 16).ok()?, u32::from_str_radix(length, 16).ok()?))
+}
+
+fn compute_checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    use super::{GdbStub, PACKET_SIZE, compute_checksum};
+    use crate::interpreter::Interpreter;
+
+    // Lado de GDB de la conexion
+    struct Client {
+        stream: TcpStream,
+    }
+
+    impl Client {
+        fn send_raw(&mut self, data: &[u8]) {
+            self.stream.write_all(data).unwrap();
+        }
+
+        fn read_byte(&mut self) -> u8 {
+            let mut byte = [0u8];
+            self.stream.read_exact(&mut byte).unwrap();
+            byte[0]
+        }
+
+        fn send(&mut self, packet: &str) {
+            self.send_raw(format!("${packet}#{:02x}", compute_checksum(packet.as_bytes())).as_bytes());
+            assert_eq!(self.read_byte(), b'+');
+        }
+
+        fn receive(&mut self) -> String {
+            assert_eq!(self.read_byte(), b'$');
+
+            let mut data = Vec::new();
+            loop {
+                match self.read_byte() {
+                    b'#' => break,
+                    byte => data.push(byte),
+                }
+            }
+
+            let checksum = [self.read_byte(), self.read_byte()];
+            let checksum = u8::from_str_radix(std::str::from_utf8(&checksum).unwrap(), 16).unwrap();
+            assert_eq!(checksum, compute_checksum(&data));
+
+            self.send_raw(b"+");
+            String::from_utf8(data).unwrap()
+        }
+
+        fn request(&mut self, packet: &str) -> String {
+            self.send(packet);
+            self.receive()
+        }
+    }
+
+    // Ejecuta session contra un stub con 16 nops en la DRAM
+    fn with_stub(session: impl FnOnce(&mut Client) + Send + 'static) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let client = thread::spawn(move || {
+            let mut client = Client {
+                stream: TcpStream::connect(address).unwrap(),
+            };
+
+            session(&mut client);
+            client.send("k");
+        });
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut interpreter = Interpreter::with_harts(1);
+        interpreter.set_pc(0x80000000);
+
+        let mut stub = GdbStub::new(&mut interpreter, stream);
+        for i in 0..16 {
+            stub.write_memory(&format!("{:x},4:13000000", 0x80000000u32 + i * 4));
+        }
+
+        assert!(stub.run().unwrap().is_none());
+        client.join().unwrap();
+    }
+
+    #[test]
+    fn bad_checksum_is_nacked() {
+        with_stub(|client| {
+            client.send_raw(b"$p20#00");
+            assert_eq!(client.read_byte(), b'-');
+
+            assert_eq!(client.request("p20"), "00000080");
+        });
+    }
+
+    #[test]
+    fn registers() {
+        with_stub(|client| {
+            assert_eq!(client.request("P1=78563412"), "OK");
+            assert_eq!(client.request("p1"), "78563412");
+
+            let registers = client.request("g");
+            assert_eq!(registers.len(), 33 * 8);
+            assert_eq!(&registers[8..16], "78563412");
+            assert_eq!(&registers[32 * 8..], "00000080");
+
+            // f0 tiene 64 bits
+            assert_eq!(client.request("P21=0100000000000000"), "OK");
+            assert_eq!(client.request("p21"), "0100000000000000");
+            assert_eq!(client.request("P21=01000000"), "E01");
+
+            assert_eq!(client.request("p2000"), "E01");
+        });
+    }
+
+    #[test]
+    fn memory() {
+        with_stub(|client| {
+            assert_eq!(client.request("M80000100,4:deadbeef"), "OK");
+            assert_eq!(client.request("m80000100,4"), "deadbeef");
+            assert_eq!(client.request("M80000100,4:dead"), "E01");
+
+            // Fuera de la memoria
+            assert_eq!(client.request("m0,4"), "E14");
+            assert_eq!(client.request("M0,1:00"), "E14");
+
+            // La respuesta se recorta al tamano de paquete
+            assert_eq!(client.request("m80000000,ffffffff").len(), PACKET_SIZE);
+        });
+    }
+
+    #[test]
+    fn breakpoints() {
+        with_stub(|client| {
+            assert_eq!(client.request("Z0,80000008,4"), "OK");
+            assert_eq!(client.request("c"), "T05swbreak:;");
+            assert_eq!(client.request("p20"), "08000080");
+
+            assert_eq!(client.request("z0,80000008,4"), "OK");
+            assert_eq!(client.request("Z0,80000010,4"), "OK");
+            assert_eq!(client.request("c"), "T05swbreak:;");
+            assert_eq!(client.request("p20"), "10000080");
+
+            // Los watchpoints no estan soportados
+            assert_eq!(client.request("Z2,80000100,4"), "");
+        });
+    }
+
+    #[test]
+    fn target_description() {
+        with_stub(|client| {
+            let first = client.request("qXfer:features:read:target.xml:0,10");
+            assert_eq!(first, "m<?xml version=\"1");
+
+            let rest = client.request("qXfer:features:read:target.xml:10,ffff");
+            assert!(rest.starts_with('l') && rest.ends_with("</target>"));
+
+            assert_eq!(client.request("qXfer:features:read:target.xml:ffffffffffffffff,ffffffffffffffff"), "l");
+        });
+    }
+
+    #[test]
+    fn vcont() {
+        with_stub(|client| {
+            assert_eq!(client.request("vCont?"), "vCont;c;C;s;S");
+
+            assert_eq!(client.request("vCont;s:1"), "S05");
+            assert_eq!(client.request("p20"), "04000080");
+
+            assert_eq!(client.request("Z0,80000010,4"), "OK");
+            assert_eq!(client.request("vCont;c"), "T05swbreak:;");
+            assert_eq!(client.request("p20"), "10000080");
+        });
+    }
+}
@@ -1,124 +1,693 @@
-use std::{fs::File, io::Read};
+use std::{
+    collections::HashSet,
+    fmt,
+    fs,
+    io,
+    rc::Rc,
+    thread,
+    time::Duration,
+};
 
-use ihex::{Reader, Record};
+use ihex::{Reader, ReaderError, Record};
 
-use crate::interpreter::{
-    bus::Bus,
-    riscv_core::{Exception, ExceptionType, InstructionType, RVCore, Trap}, virtual_memory::sv32::{AccessType, PhysicalAddress, translate_address},
+use crate::{
+    interpreter::{
+        block_cache::{Block, BlockCursor, DecodedInstr, decode_block},
+        bus::Bus,
+        device::Device,
+        disassembler::disassemble,
+        elf::{Elf, ElfError, SymbolTable},
+        extensions::rv32c::is_compressed,
+        riscv_core::{InstructionType, InterruptType, RVCore, Trap},
+        snapshot::{SnapshotError, SnapshotReader, SnapshotWriter},
+        trace::Tracer,
+        virtual_memory::{
+            pmp::check_pmp,
+            sv32::{AccessType, PhysicalAddress, translate_address},
+        },
+    },
+    peripherals::{htif::Htif, plic::Plic},
 };
 
+mod block_cache;
 mod bus;
+mod config;
 mod csr;
+pub mod device;
+pub mod disassembler;
+pub mod elf;
 mod extensions;
+pub mod gdb;
+pub mod snapshot;
+mod softfloat;
+mod trace;
 mod virtual_memory;
 mod riscv_core;
 
-const NUM_HARTS: usize = 1;
+pub use config::MachineConfig;
+pub use riscv_core::{Exception, ExceptionType};
+pub use virtual_memory::access::MisalignedAccess;
+
+// Instrucciones que ejecuta cada hart antes de pasar al siguiente
+const HART_QUANTUM: u64 = 100;
+// Tiempo que se duerme el hilo cuando todos los harts esperan en wfi y ningun
+// timer los va a despertar
+const IDLE_SLEEP: Duration = Duration::from_micros(100);
+
+// Error al cargar una imagen en la memoria
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Hex(ReaderError),
+    Elf(ElfError),
+    // La imagen escribe fuera de la memoria
+    OutOfMemory(u64),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Hex(err) => write!(f, "fichero .hex no valido: {err}"),
+            Self::Elf(err) => write!(f, "{err}"),
+            Self::OutOfMemory(address) => write!(f, "la imagen no cabe en la memoria: {address:#010X}"),
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+// Motivo por el que run/run_for han devuelto el control
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitStatus {
+    // El programa ha terminado por HTIF
+    Halted { exit_code: u32 },
+    // Se ha ejecutado el numero de instrucciones pedido
+    InstructionLimit,
+    // pc ha llegado a un breakpoint. La instruccion aun no se ha ejecutado
+    Breakpoint { pc: u32 },
+    // La primera instruccion del manejador de una excepcion vuelve a fallar, por
+    // lo que el programa se quedaria en un bucle infinito
+    FatalError { pc: u32, exception: Exception },
+}
 
-#[derive(Default)]
 pub struct Interpreter {
+    config: MachineConfig,
     pub bus: Bus,
-    core: RVCore,
+    // Los harts comparten el bus y se ejecutan por turnos de HART_QUANTUM
+    // instrucciones
+    cores: Vec<RVCore>,
+    current_hart: usize,
+    quantum_left: u64,
+
+    // Simbolos del ultimo ELF cargado
+    symbols: SymbolTable,
+
+    breakpoints: HashSet<u32>,
+    // Al continuar desde un breakpoint hay que ejecutar su instruccion
+    resume_from_breakpoint: bool,
+
+    tracer: Option<Tracer>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::with_harts(1)
+    }
 }
 
 impl Interpreter {
-    #[cfg(test)]
-    pub fn new_test(to_host: usize) -> Self {
-        Self {
-            bus: Bus::new_test(to_host),
-            core: RVCore::default(),
-        }
+    // Para programas sin tabla de simbolos (.hex, .bin) que terminan por HTIF
+    pub fn with_htif(to_host: usize, from_host: usize) -> Self {
+        let mut interpreter = Self::default();
+        interpreter.set_htif(to_host, from_host);
+
+        interpreter
+    }
+
+    pub fn set_htif(&mut self, to_host: usize, from_host: usize) {
+        self.bus.htif = Some(Htif::new(to_host, from_host));
+    }
+
+    pub fn set_misaligned_access(&mut self, misaligned_access: MisalignedAccess) {
+        self.bus.misaligned_access = misaligned_access;
+    }
+
+    // Conecta un dispositivo en [base, base + size). Si tiene irq sus
+    // interrupciones llegan al PLIC por esa linea. Falla si la region se
+    // solapa con otra o no cabe en el espacio de direcciones
+    pub fn add_device(&mut self, base: u32, size: u32, irq: Option<u32>, device: Box<dyn Device>) -> Result<(), String> {
+        self.bus.add_device(base as u64, size as u64, irq, device)
+    }
+
+    // Reinicia los harts y los dispositivos. La memoria y los breakpoints se
+    // mantienen, asi que se puede volver a ejecutar el programa cargado
+    pub fn reset(&mut self) {
+        self.cores = (0..self.config.num_harts as u32).map(RVCore::new).collect();
+        self.current_hart = 0;
+        self.quantum_left = HART_QUANTUM;
+        self.resume_from_breakpoint = false;
+        self.bus.reset();
+        self.set_pc(self.config.dram_base);
     }
 
     #[cfg(not(test))]
     pub fn new() -> Self {
-        Self {
-            bus: Bus::default(),
-            core: RVCore::default(),
-        }
+        Self::default()
     }
 
-    pub fn load_hex(&mut self, path: &str) {
-        let mut file = File::open(path).unwrap();
-        let mut buf = String::new();
+    pub fn with_harts(num_harts: usize) -> Self {
+        Self::with_config(MachineConfig {
+            num_harts,
+            ..MachineConfig::default()
+        })
+    }
+
+    // Todos los harts empiezan al principio de la DRAM, cada uno con su
+    // mhartid. Entra en panico si la configuracion no es valida
+    pub fn with_config(config: MachineConfig) -> Self {
+        let mut interpreter = Self {
+            bus: Bus::new(&config),
+            cores: (0..config.num_harts as u32).map(RVCore::new).collect(),
+            current_hart: 0,
+            quantum_left: HART_QUANTUM,
+            symbols: SymbolTable::default(),
+            breakpoints: HashSet::new(),
+            resume_from_breakpoint: false,
+            tracer: None,
+            config,
+        };
+        interpreter.set_pc(interpreter.config.dram_base);
 
-        file.read_to_string(&mut buf).unwrap();
+        interpreter
+    }
 
-        let reader = Reader::new(&buf);
+    pub fn get_config(&self) -> &MachineConfig {
+        &self.config
+    }
 
-        for i in reader {
-            let record = i.unwrap();
+    pub fn get_num_harts(&self) -> usize {
+        self.cores.len()
+    }
 
-            if let Record::Data { offset, value } = record {
-                value.iter().enumerate().for_each(|(add, val)| {
-                    let _ = self
-                        .bus
-                        .write_byte(&PhysicalAddress(0x80000000u64 + offset as u64 + add as u64), *val);
-                });
+    // Hart que ha ejecutado la ultima instruccion o que ejecutara la siguiente.
+    // Los breakpoints y errores de run se refieren a este hart
+    pub fn get_current_hart(&self) -> usize {
+        self.current_hart
+    }
+
+    fn core(&self) -> &RVCore {
+        &self.cores[self.current_hart]
+    }
+
+    fn core_mut(&mut self) -> &mut RVCore {
+        &mut self.cores[self.current_hart]
+    }
+
+    pub fn load_hex(&mut self, path: &str) -> Result<(), LoadError> {
+        self.load_hex_at(path, self.config.dram_base)
+    }
+
+    // Los desplazamientos de los registros del .hex son relativos a base
+    pub fn load_hex_at(&mut self, path: &str, base: u32) -> Result<(), LoadError> {
+        let buf = fs::read_to_string(path)?;
+
+        for record in Reader::new(&buf) {
+            if let Record::Data { offset, value } = record.map_err(LoadError::Hex)? {
+                self.write_image(base as u64 + offset as u64, value)?;
             }
         }
+
+        Ok(())
+    }
+
+    pub fn load_bin(&mut self, path: &str) -> Result<(), LoadError> {
+        self.load_bin_at(path, self.config.dram_base)
+    }
+
+    pub fn load_bin_at(&mut self, path: &str, address: u32) -> Result<(), LoadError> {
+        let buf = fs::read(path)?;
+
+        self.write_image(address as u64, buf)
+    }
+
+    // Escribe los bytes de una imagen a partir de address. Falla en el primer
+    // byte que no se puede escribir
+    fn write_image(&mut self, address: u64, bytes: impl IntoIterator<Item = u8>) -> Result<(), LoadError> {
+        for (i, val) in bytes.into_iter().enumerate() {
+            let phys_address = PhysicalAddress(address + i as u64);
+
+            self.bus
+                .write_byte(&phys_address, val)
+                .map_err(|_| LoadError::OutOfMemory(phys_address.0))?;
+        }
+
+        Ok(())
+    }
+
+    // pc del hart actual
+    pub fn get_pc(&self) -> u32 {
+        self.core().pc
+    }
+
+    // Todos los harts empiezan a ejecutar en pc
+    pub fn set_pc(&mut self, pc: u32) {
+        for core in &mut self.cores {
+            core.pc = pc;
+        }
+    }
+
+    // Convencion de arranque de OpenSBI y Linux: a0 contiene el mhartid y a1 la
+    // direccion del device tree
+    pub fn set_boot_args(&mut self, dtb_address: u32) {
+        for core in &mut self.cores {
+            core.write_reg(10, core.get_hartid() as u32);
+            core.write_reg(11, dtb_address);
+        }
+    }
+
+    // Carga cada segmento PT_LOAD en su direccion fisica y empieza a ejecutar en
+    // el punto de entrada del ELF
+    pub fn load_elf(&mut self, path: &str) -> Result<(), LoadError> {
+        let buf = fs::read(path)?;
+        let elf = Elf::parse(&buf).map_err(LoadError::Elf)?;
+
+        for segment in &elf.segments {
+            let data = segment.data.iter().copied();
+            // Lo que no esta en el fichero (.bss) se rellena con ceros
+            let zeros = std::iter::repeat_n(0, (segment.mem_size as usize).saturating_sub(segment.data.len()));
+
+            self.write_image(segment.phys_address as u64, data.chain(zeros))?;
+        }
+
+        // Si el ELF define tohost se usa para el HTIF
+        if let Some(to_host) = elf.symbols.get_address("tohost") {
+            let from_host = elf.symbols.get_address("fromhost").unwrap_or(to_host + 8);
+
+            self.bus.htif = Some(Htif::new(to_host as usize, from_host as usize));
+        }
+
+        for core in &mut self.cores {
+            core.pc = elf.entry;
+        }
+
+        self.symbols = elf.symbols;
+
+        Ok(())
+    }
+
+    pub fn get_symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    // Guarda el estado de la maquina: harts, memoria y dispositivos. Los
+    // breakpoints, los simbolos y la traza son del depurador y no se guardan
+    pub fn save_snapshot(&self, path: &str) -> io::Result<()> {
+        let mut writer = SnapshotWriter::new();
+
+        writer.write_u32(self.cores.len() as u32);
+        writer.write_u32(self.current_hart as u32);
+        writer.write_u64(self.quantum_left);
+
+        for core in &self.cores {
+            core.save_state(&mut writer);
+        }
+
+        self.bus.save_state(&mut writer);
+
+        fs::write(path, writer.into_bytes())
     }
 
-    pub fn load_bin(&mut self, path: &str) {
-        let mut file = File::open(path).unwrap();
-        let mut buf: Vec<u8> = Vec::new();
+    // El interprete tiene que tener el mismo numero de harts que la instantanea.
+    // Si falla a mitad de la carga el estado de la maquina queda a medias
+    pub fn load_snapshot(&mut self, path: &str) -> Result<(), SnapshotError> {
+        let data = fs::read(path)?;
+        let mut reader = SnapshotReader::new(&data)?;
+
+        reader.expect_u32("numero de harts", self.cores.len() as u32)?;
+
+        let current_hart = reader.read_u32()? as usize;
+        if current_hart >= self.cores.len() {
+            return Err(SnapshotError::Corrupted);
+        }
+
+        self.current_hart = current_hart;
+        self.quantum_left = reader.read_u64()?;
 
+        for core in &mut self.cores {
+            core.load_state(&mut reader)?;
+        }
 
-        file.read_to_end(&mut buf).unwrap();
+        self.bus.load_state(&mut reader)?;
 
-        for (i, val) in buf.iter().enumerate() {
-            let _ = self.bus.write_byte(&PhysicalAddress(0x80000000u64 + i as u64), *val);
+        if !reader.is_empty() {
+            return Err(SnapshotError::Corrupted);
         }
+
+        self.resume_from_breakpoint = false;
+
+        Ok(())
     }
 
     pub fn fetch(&mut self) -> Result<u32, Exception> {
-        let pc = self.core.pc;
-        let phys_pc = translate_address(&mut self.core, &mut self.bus, pc, AccessType::Execute)?;
+        self.fetch_at(self.core().pc)
+    }
+
+    fn fetch_at(&mut self, address: u32) -> Result<u32, Exception> {
+        let low = self.fetch_parcel(address)? as u32;
 
-        // println!("{:#016X}", phys_pc.0);
-        if phys_pc.0 == 0x80002948 {
-            println!("TEST");
+        if is_compressed(low) {
+            return Ok(low);
         }
 
-        let val = self
-            .bus
-            .read_aligned_word(&phys_pc)
-            .map_err(|_| Exception::new(ExceptionType::InstructionAccessFault, pc))?;
+        // Una instruccion de 32 bits puede estar partida entre dos paginas
+        let high = self.fetch_parcel(address.wrapping_add(2))? as u32;
+
+        Ok((high << 16) | low)
+    }
+
+    // Devuelve la instruccion en la direccion virtual address y su texto en
+    // ensamblador. La direccion se traduce con el modo de privilegio actual
+    pub fn disassemble_at(&mut self, address: u32) -> Result<(u32, String), Exception> {
+        let instr = self.fetch_at(address)?;
+
+        Ok((instr, disassemble(instr)))
+    }
+
+    fn fetch_parcel(&mut self, address: u32) -> Result<u16, Exception> {
+        let phys_address =
+            translate_address(&mut self.cores[self.current_hart], &mut self.bus, address, AccessType::Execute)?;
 
-        Ok(val)
+        self.bus
+            .read_halfword(&phys_address)
+            .map_err(|_| Exception::new(ExceptionType::InstructionAccessFault, address))
     }
 
     pub fn decode(&mut self, instr: u32) -> Option<InstructionType> {
-        self.core.decode(instr)
+        self.core_mut().decode(instr)
+    }
+
+    // Instruccion decodificada en el pc del hart actual: el bloque y su
+    // posicion en el
+    fn fetch_decoded(&mut self) -> Result<(Rc<Block>, usize), Exception> {
+        // Escrituras hechas fuera de step (al cargar un programa o desde GDB).
+        // Las ven todos los harts
+        if self.bus.has_stale_code_pages() {
+            let pages = self.bus.take_stale_code_pages();
+
+            for core in &mut self.cores {
+                for page in &pages {
+                    core.block_cache.invalidate_page(*page);
+                }
+
+                // No se sabe en que pagina esta el bloque en curso
+                core.block_cursor = None;
+            }
+        }
+
+        let core = &mut self.cores[self.current_hart];
+        let pc = core.pc;
+
+        let (block, index, phys_address) = match core.block_cursor.take() {
+            Some(cursor) if cursor.pc == pc => {
+                // La primera instruccion se comprueba al traducir pc
+                let len = cursor.block.instrs[cursor.index].len as u64;
+
+                if !check_pmp(&core.control_and_status, cursor.phys_address, len, AccessType::Execute, core.privilege_level) {
+                    return Err(Exception::new(ExceptionType::InstructionAccessFault, pc));
+                }
+
+                (cursor.block, cursor.index, cursor.phys_address)
+            }
+            _ => {
+                let (block, phys_address) = self.lookup_block(pc)?;
+                (block, 0, phys_address)
+            }
+        };
+
+        if index + 1 < block.instrs.len() {
+            let len = block.instrs[index].len;
+
+            self.cores[self.current_hart].block_cursor = Some(BlockCursor {
+                block: block.clone(),
+                index: index + 1,
+                pc: pc.wrapping_add(len),
+                phys_address: phys_address + len as u64,
+            });
+        }
+
+        Ok((block, index))
     }
 
+    // Bloque que empieza en pc y su direccion fisica
+    fn lookup_block(&mut self, pc: u32) -> Result<(Rc<Block>, u64), Exception> {
+        let core = &mut self.cores[self.current_hart];
+        let phys_address = translate_address(core, &mut self.bus, pc, AccessType::Execute)?;
+
+        if let Some(block) = core.block_cache.get(phys_address.0) {
+            return Ok((block, phys_address.0));
+        }
+
+        if let Some(block) = decode_block(core, &mut self.bus, phys_address.0) {
+            let block = Rc::new(block);
+            self.bus.mark_code_page(phys_address.0);
+            core.block_cache.insert(phys_address.0, block.clone());
+
+            return Ok((block, phys_address.0));
+        }
+
+        // Fuera de la DRAM o cruzando de pagina se decodifica sin guardarla
+        let raw = self.fetch()?;
+        let instr = self.decode(raw).ok_or(Exception::new(ExceptionType::IllegalInstruction, raw))?;
+        let len = if is_compressed(raw) { 2 } else { 4 };
+
+        Ok((Rc::new(Block { instrs: vec![DecodedInstr { instr, raw, len }] }), phys_address.0))
+    }
+
+    // Escribe en path la traza de las instrucciones que se ejecuten a partir de ahora
+    pub fn enable_trace(&mut self, path: &str) -> io::Result<()> {
+        self.tracer = Some(Tracer::new(path)?);
+
+        Ok(())
+    }
+
+    // Ejecuta una instruccion en el hart actual
     pub fn step(&mut self) -> Result<(), Exception> {
-        let fetched = self.fetch()?;
-        let mut instr = self.decode(fetched).ok_or(Exception::new(ExceptionType::IllegalInstruction, fetched))?;
+        let pc = self.core().pc;
+        let priv_level = self.core().privilege_level;
+
+        if self.tracer.is_some() {
+            Tracer::begin(&mut self.cores[self.current_hart], &mut self.bus);
+        }
+
+        let (block, index) = match self.fetch_decoded() {
+            Ok(decoded) => decoded,
+            Err(exception) => {
+                // Si no se ha podido decodificar tval es la instruccion
+                let instr = (exception.get_type() == ExceptionType::IllegalInstruction).then(|| {
+                    let raw = exception.get_tval();
+                    (raw, if is_compressed(raw) { 2 } else { 4 })
+                });
+
+                self.trace(|tracer, core, bus| tracer.trap(core, bus, pc, instr, exception));
+                return Err(exception);
+            }
+        };
+        let DecodedInstr { instr, raw, len } = &block.instrs[index];
+
+        let core = &mut self.cores[self.current_hart];
+        core.next_pc = core.pc.wrapping_add(*len);
+
+        if let Err(exception) = instr.execute(&mut self.bus, core) {
+            core.block_cursor = None;
+            self.trace(|tracer, core, bus| tracer.trap(core, bus, pc, Some((*raw, *len)), exception));
+            return Err(exception);
+        }
 
-        instr.execute(&mut self.bus, &mut self.core)?;
+        // Las escrituras del hart en paginas con instrucciones decodificadas
+        // solo descartan sus bloques. Los demas harts no las ven hasta que
+        // ejecutan fence.i
+        if self.bus.has_stale_code_pages() {
+            for page in self.bus.take_stale_code_pages() {
+                core.block_cache.invalidate_page(page);
+            }
 
-        self.core.control_and_status.increment_minstret();
-        self.core.pc = self.core.pc.wrapping_add(4);
+            core.block_cursor = None;
+        }
+
+        self.trace(|tracer, core, bus| tracer.commit(core, bus, pc, priv_level, *raw, *len));
+
+        let core = self.core_mut();
+        core.pc = core.next_pc;
 
         Ok(())
     }
 
-    #[cfg(test)]
-    pub fn read_test_result(&self, to_host: usize) -> u32 {
-        let val_1 = self.bus.dram[to_host - 0x80000000];
-        let val_2 = self.bus.dram[to_host - 0x80000000 + 1];
-        let val_3 = self.bus.dram[to_host - 0x80000000 + 2];
-        let val_4 = self.bus.dram[to_host - 0x80000000 + 3];
+    // Si no se puede escribir la traza se avisa y se deja de trazar en lugar
+    // de parar la ejecucion
+    fn trace(&mut self, write: impl FnOnce(&mut Tracer, &mut RVCore, &mut Bus) -> io::Result<()>) {
+        let Some(tracer) = &mut self.tracer else {
+            return;
+        };
+
+        if let Err(err) = write(tracer, &mut self.cores[self.current_hart], &mut self.bus) {
+            eprintln!("No se pudo escribir la traza, se desactiva: {err}");
+            self.tracer = None;
+        }
+    }
+
+    // Actualiza los bits de mip del hart actual que dependen de los dispositivos
+    // y su copia de mtime. Las IPI se envian escribiendo en el msip del CLINT del
+    // hart destino
+    fn update_interrupts(&mut self) {
+        self.bus.update_interrupts();
+
+        let hart_id = self.current_hart;
+        let clint = &self.bus.clint;
+        let plic = &self.bus.plic;
+        let csrs = &mut self.cores[hart_id].control_and_status;
+
+        csrs.set_time(clint.get_mtime());
+        csrs.set_interrupt_pending(InterruptType::MachineSwInt, clint.is_software_interrupt_pending(hart_id));
+        csrs.set_interrupt_pending(InterruptType::MachineTimerInt, clint.is_timer_interrupt_pending(hart_id));
+        csrs.set_interrupt_pending(
+            InterruptType::MachineExternalInt,
+            plic.has_interrupt(Plic::machine_context(hart_id)),
+        );
+        csrs.set_interrupt_pending(
+            InterruptType::SupervisorExternalInt,
+            plic.has_interrupt(Plic::supervisor_context(hart_id)),
+        );
+    }
+
+    pub fn add_breakpoint(&mut self, address: u32) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u32) {
+        self.breakpoints.remove(&address);
+    }
+
+    // Ejecuta una sola instruccion aunque pc este en un breakpoint
+    pub fn step_instruction(&mut self) -> ExitStatus {
+        self.resume_from_breakpoint = true;
+        self.run_for(1)
+    }
 
-        u32::from_le_bytes([val_1, val_2, val_3, val_4])
+    // Ejecuta hasta que el programa termina, llega a un breakpoint o hay un error
+    pub fn run(&mut self) -> ExitStatus {
+        self.run_until(None)
     }
 
-    pub fn run(&mut self) {
+    // Como run, pero ejecuta como mucho n instrucciones
+    pub fn run_for(&mut self, n: u64) -> ExitStatus {
+        self.run_until(Some(n))
+    }
+
+    fn run_until(&mut self, limit: Option<u64>) -> ExitStatus {
+        let mut executed = 0;
+
+        // Entre dos llamadas se puede haber cambiado el estado de los harts
+        // desde fuera (por ejemplo satp desde GDB)
+        for core in &mut self.cores {
+            core.block_cursor = None;
+        }
+
         loop {
-            if let Err(exception) = self.step() {
-                Trap::Exception(exception).handle(&mut self.core);
+            if limit.is_some_and(|limit| executed >= limit) {
+                return ExitStatus::InstructionLimit;
+            }
+
+            // Se cambia de hart antes de ejecutar para que tras parar el hart
+            // actual sea el de la ultima instruccion
+            if self.quantum_left == 0 {
+                self.current_hart = (self.current_hart + 1) % self.cores.len();
+                self.quantum_left = HART_QUANTUM;
+            }
+
+            self.bus.tick();
+            self.update_interrupts();
+
+            // wfi termina con cualquier interrupcion pendiente y habilitada en
+            // mie, aunque no se atienda
+            if self.core().control_and_status.read_enabled_interrupts() != 0 {
+                self.core_mut().waiting_for_interrupt = false;
+            }
+
+            // Las interrupciones se atienden entre instrucciones
+            if let Some(interrupt) = self.core().get_pending_interrupt() {
+                Trap::Interrupt(interrupt).handle(self.core_mut());
+                self.core_mut().block_cursor = None;
+            }
+
+            // Las iteraciones en espera cuentan para el limite para que GDB
+            // pueda interrumpir a un hart dormido
+            if self.core().waiting_for_interrupt {
+                self.idle();
+                self.core_mut().control_and_status.increment_counters(false);
+                executed += 1;
+                self.quantum_left = 0;
+                continue;
+            }
+
+            let pc = self.core().pc;
+
+            if self.breakpoints.contains(&pc) && !self.resume_from_breakpoint {
+                self.resume_from_breakpoint = true;
+                return ExitStatus::Breakpoint { pc };
+            }
+
+            self.resume_from_breakpoint = false;
+
+            let result = self.step();
+            self.core_mut().control_and_status.increment_counters(result.is_ok());
+
+            if let Err(exception) = result {
+                Trap::Exception(exception).handle(self.core_mut());
+
+                if self.core().pc == pc {
+                    return ExitStatus::FatalError { pc, exception };
+                }
             };
+
+            executed += 1;
+            self.quantum_left -= 1;
+
+            if let Some(exit_code) = self.bus.htif.as_ref().and_then(Htif::get_exit_code) {
+                return ExitStatus::Halted { exit_code };
+            }
+        }
+    }
+
+    // Se llama en cada iteracion en la que el hart actual esta en wfi. Si hay
+    // algun hart que pueda ejecutar no se hace nada. Si todos esperan se adelanta
+    // mtime hasta el siguiente timer que los pueda despertar y, si no hay
+    // ninguno, se duerme el hilo para no gastar CPU
+    fn idle(&mut self) {
+        if !self.cores.iter().all(|core| core.waiting_for_interrupt) {
+            return;
+        }
+
+        let clint = &self.bus.clint;
+        let mtime = clint.get_mtime();
+
+        let next_timer = self
+            .cores
+            .iter()
+            .enumerate()
+            .filter(|(_, core)| core.control_and_status.is_interrupt_enabled(InterruptType::MachineTimerInt))
+            .map(|(hart_id, _)| clint.get_mtimecmp(hart_id))
+            .filter(|&mtimecmp| mtimecmp > mtime && mtimecmp != u64::MAX)
+            .min();
+
+        match next_timer {
+            // El siguiente tick deja mtime en mtimecmp
+            Some(mtimecmp) => self.bus.clint.set_mtime(mtimecmp - 1),
+            None => thread::sleep(IDLE_SLEEP),
         }
     }
 }
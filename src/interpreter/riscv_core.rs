@@ -1,5 +1,7 @@
+use crate::interpreter::block_cache::{BlockCache, BlockCursor};
 use crate::interpreter::bus::Bus;
 use crate::interpreter::csr::ControlAndStatus;
+use crate::interpreter::csr::HpmEvent;
 use crate::interpreter::csr::MCAUSE;
 use crate::interpreter::csr::MEDELEG;
 use crate::interpreter::csr::MEDELEGH;
@@ -13,41 +15,84 @@ use crate::interpreter::csr::SEPC;
 use crate::interpreter::csr::SSTATUS;
 use crate::interpreter::csr::STVAL;
 use crate::interpreter::csr::STVEC;
+use crate::interpreter::csr::TVEC_BASE_MASK;
+use crate::interpreter::csr::TVEC_MODE_MASK;
+use crate::interpreter::csr::TVEC_MODE_VECTORED;
 use crate::interpreter::extensions::rv32a::*;
+use crate::interpreter::extensions::rv32c::{expand_compressed, is_compressed};
+use crate::interpreter::extensions::rv32d::*;
+use crate::interpreter::extensions::rv32f::*;
 use crate::interpreter::extensions::rv32i::*;
 use crate::interpreter::extensions::rv32m::*;
 use crate::interpreter::extensions::rv32privileged::*;
 use crate::interpreter::extensions::rv32zicrs::*;
 use crate::interpreter::extensions::rv32zifencei::fence_i;
+use crate::interpreter::snapshot::{SnapshotError, SnapshotReader, SnapshotWriter};
+use crate::interpreter::trace::CommitLog;
+use crate::interpreter::virtual_memory::tlb::Tlb;
 
 pub struct RVCore {
     // x0/zero -> Siempre 0
     registers: [u32; 32],
+    // Los valores de precision simple van encapsulados en NaN (NaN-boxing)
+    fregisters: [u64; 32],
     pub pc: u32,
+    // Direccion de la siguiente instruccion, los saltos la sobreescriben
+    pub next_pc: u32,
 
     pub control_and_status: ControlAndStatus,
 
     pub privilege_level: PrivilegeLevel,
 
     hart_id: u32,
+
+    pub tlb: Tlb,
+
+    pub block_cache: BlockCache,
+    pub block_cursor: Option<BlockCursor>,
+
+    // Ha ejecutado wfi y no tiene ninguna interrupcion pendiente
+    pub waiting_for_interrupt: bool,
+
+    // Registros y accesos a memoria de la instruccion en curso, solo con la traza activa
+    pub commit_log: Option<CommitLog>,
 }
 
 impl Default for RVCore {
     fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl RVCore {
+    pub fn new(hart_id: u32) -> Self {
         Self {
             registers: [0u32; 32],
+            fregisters: [0u64; 32],
             pc: 0x80000000,
-            control_and_status: ControlAndStatus::new(0),
+            next_pc: 0x80000000,
+            control_and_status: ControlAndStatus::new(hart_id),
 
             privilege_level: PrivilegeLevel::Machine,
 
-            hart_id: 0,
+            hart_id,
+
+            tlb: Tlb::default(),
+
+            block_cache: BlockCache::default(),
+            block_cursor: None,
+
+            waiting_for_interrupt: false,
+
+            commit_log: None,
         }
     }
-}
 
-impl RVCore {
     pub fn decode(&mut self, instr: u32) -> Option<InstructionType> {
+        if is_compressed(instr) {
+            return expand_compressed(instr as u16).and_then(|expanded| self.decode(expanded));
+        }
+
         let opcode = instr & 0x7F;
 
         match opcode {
@@ -65,6 +110,12 @@ impl RVCore {
             0b1101111 => self.try_decode_j_instr(instr).map(InstructionType::J),
             0b1110011 => self.try_decode_system_instr(instr).map(InstructionType::I),
             0b0001111 => self.try_decode_fence_instr(instr).map(InstructionType::I),
+            0b0000111 => self.try_decode_load_fp_instr(instr).map(InstructionType::F),
+            0b0100111 => self.try_decode_store_fp_instr(instr).map(InstructionType::F),
+            0b1010011 => self.try_decode_op_fp_instr(instr).map(InstructionType::F),
+            0b1000011 | 0b1000111 | 0b1001011 | 0b1001111 => {
+                self.try_decode_fused_fp_instr(instr).map(InstructionType::F)
+            }
 
             _ => None,
         }
@@ -81,11 +132,36 @@ impl RVCore {
     pub fn write_reg(&mut self, reg: u32, val: u32) {
         if reg > 0 && reg < 32 {
             self.registers[reg as usize] = val;
+
+            if let Some(log) = &mut self.commit_log {
+                log.regs.push((reg, val));
+            }
         } else if reg >= 32 {
             unreachable!("MAL REGISTRO")
         }
     }
 
+    pub fn read_freg(&self, reg: u32) -> u64 {
+        if reg < 32 {
+            self.fregisters[reg as usize]
+        } else {
+            unreachable!("MAL REGISTRO")
+        }
+    }
+
+    pub fn write_freg(&mut self, reg: u32, val: u64) {
+        if reg < 32 {
+            self.fregisters[reg as usize] = val;
+            self.control_and_status.set_fs_dirty();
+
+            if let Some(log) = &mut self.commit_log {
+                log.fregs.push((reg, val));
+            }
+        } else {
+            unreachable!("MAL REGISTRO")
+        }
+    }
+
     fn try_decode_r_instr(&mut self, instr: u32) -> Option<RInstruction> {
         let rd = (instr >> 7) & 0x1F;
         let funct3 = (instr >> 12) & 0x7;
@@ -359,6 +435,7 @@ impl RVCore {
                 
                 // Tecnicamente son instrucciones R, pero asi me simplifica la vida
                 (0x102, 0, 0) => Some(IInstruction::new(instr, rs1, csr, rd, sret)),
+                (0x105, 0, 0) => Some(IInstruction::new(instr, rs1, csr, rd, wfi)),
                 (0x120..=0x13F, _, _) => Some(IInstruction::new(instr, rs1, csr, rd, sfence_vma)),
                 (0x302, 0, 0) => Some(IInstruction::new(instr, rs1, csr, rd, mret)),
                 _ => None,
@@ -387,9 +464,205 @@ impl RVCore {
         }
     }
 
+    fn try_decode_load_fp_instr(&mut self, instr: u32) -> Option<FInstruction> {
+        let funct3 = (instr >> 12) & 0x7;
+        let imm_val = get_i_imm_val(instr);
+
+        match funct3 {
+            0x2 => Some(FInstruction::new(instr, imm_val, flw)),
+            0x3 => Some(FInstruction::new(instr, imm_val, fld)),
+
+            _ => None,
+        }
+    }
+
+    fn try_decode_store_fp_instr(&mut self, instr: u32) -> Option<FInstruction> {
+        let funct3 = (instr >> 12) & 0x7;
+        let imm_val = get_s_imm_val(instr);
+
+        match funct3 {
+            0x2 => Some(FInstruction::new(instr, imm_val, fsw)),
+            0x3 => Some(FInstruction::new(instr, imm_val, fsd)),
+
+            _ => None,
+        }
+    }
+
+    fn try_decode_op_fp_instr(&mut self, instr: u32) -> Option<FInstruction> {
+        let rm = (instr >> 12) & 0x7;
+        let rs2 = (instr >> 20) & 0x1F;
+        let fmt = (instr >> 25) & 0x3;
+        let funct5 = (instr >> 27) & 0x1F;
+
+        let function: fn(&FInstruction, &mut Bus, &mut RVCore) -> Result<(), Exception> = match (funct5, fmt, rm, rs2) {
+            (0b00000, 0b00, _, _) => fadd_s,
+            (0b00000, 0b01, _, _) => fadd_d,
+            (0b00001, 0b00, _, _) => fsub_s,
+            (0b00001, 0b01, _, _) => fsub_d,
+            (0b00010, 0b00, _, _) => fmul_s,
+            (0b00010, 0b01, _, _) => fmul_d,
+            (0b00011, 0b00, _, _) => fdiv_s,
+            (0b00011, 0b01, _, _) => fdiv_d,
+            (0b01011, 0b00, _, 0) => fsqrt_s,
+            (0b01011, 0b01, _, 0) => fsqrt_d,
+
+            (0b00100, 0b00, 0b000, _) => fsgnj_s,
+            (0b00100, 0b00, 0b001, _) => fsgnjn_s,
+            (0b00100, 0b00, 0b010, _) => fsgnjx_s,
+            (0b00100, 0b01, 0b000, _) => fsgnj_d,
+            (0b00100, 0b01, 0b001, _) => fsgnjn_d,
+            (0b00100, 0b01, 0b010, _) => fsgnjx_d,
+
+            (0b00101, 0b00, 0b000, _) => fmin_s,
+            (0b00101, 0b00, 0b001, _) => fmax_s,
+            (0b00101, 0b01, 0b000, _) => fmin_d,
+            (0b00101, 0b01, 0b001, _) => fmax_d,
+
+            // FCVT.S.D / FCVT.D.S
+            (0b01000, 0b00, _, 1) => fcvt_s_d,
+            (0b01000, 0b01, _, 0) => fcvt_d_s,
+
+            (0b10100, 0b00, 0b010, _) => feq_s,
+            (0b10100, 0b00, 0b001, _) => flt_s,
+            (0b10100, 0b00, 0b000, _) => fle_s,
+            (0b10100, 0b01, 0b010, _) => feq_d,
+            (0b10100, 0b01, 0b001, _) => flt_d,
+            (0b10100, 0b01, 0b000, _) => fle_d,
+
+            (0b11000, 0b00, _, 0) => fcvt_w_s,
+            (0b11000, 0b00, _, 1) => fcvt_wu_s,
+            (0b11000, 0b01, _, 0) => fcvt_w_d,
+            (0b11000, 0b01, _, 1) => fcvt_wu_d,
+            (0b11010, 0b00, _, 0) => fcvt_s_w,
+            (0b11010, 0b00, _, 1) => fcvt_s_wu,
+            (0b11010, 0b01, _, 0) => fcvt_d_w,
+            (0b11010, 0b01, _, 1) => fcvt_d_wu,
+
+            (0b11100, 0b00, 0b000, 0) => fmv_x_w,
+            (0b11100, 0b00, 0b001, 0) => fclass_s,
+            (0b11100, 0b01, 0b001, 0) => fclass_d,
+            (0b11110, 0b00, 0b000, 0) => fmv_w_x,
+
+            _ => return None,
+        };
+
+        Some(FInstruction::new(instr, 0, function))
+    }
+
+    fn try_decode_fused_fp_instr(&mut self, instr: u32) -> Option<FInstruction> {
+        let opcode = instr & 0x7F;
+        let fmt = (instr >> 25) & 0x3;
+
+        let function: fn(&FInstruction, &mut Bus, &mut RVCore) -> Result<(), Exception> = match (opcode, fmt) {
+            (0b1000011, 0b00) => fmadd_s,
+            (0b1000111, 0b00) => fmsub_s,
+            (0b1001011, 0b00) => fnmsub_s,
+            (0b1001111, 0b00) => fnmadd_s,
+            (0b1000011, 0b01) => fmadd_d,
+            (0b1000111, 0b01) => fmsub_d,
+            (0b1001011, 0b01) => fnmsub_d,
+            (0b1001111, 0b01) => fnmadd_d,
+
+            _ => return None,
+        };
+
+        Some(FInstruction::new(instr, 0, function))
+    }
+
     pub fn get_hartid(&self) -> usize {
         self.hart_id as usize
     }
+
+    // Descarta las instrucciones decodificadas del hart
+    pub fn flush_block_cache(&mut self) {
+        self.block_cache.clear();
+        self.block_cursor = None;
+    }
+
+    pub fn count_event(&mut self, event: HpmEvent) {
+        self.control_and_status.count_event(event, self.privilege_level);
+    }
+
+    pub fn save_state(&self, writer: &mut SnapshotWriter) {
+        writer.write_u32(self.hart_id);
+
+        for reg in self.registers {
+            writer.write_u32(reg);
+        }
+
+        for freg in self.fregisters {
+            writer.write_u64(freg);
+        }
+
+        writer.write_u32(self.pc);
+        writer.write_u8(self.privilege_level as u8);
+        writer.write_bool(self.waiting_for_interrupt);
+
+        self.control_and_status.save_state(writer);
+    }
+
+    // La TLB no se guarda, se vacia como tras un sfence.vma
+    pub fn load_state(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        reader.expect_u32("mhartid", self.hart_id)?;
+
+        for reg in &mut self.registers {
+            *reg = reader.read_u32()?;
+        }
+
+        for freg in &mut self.fregisters {
+            *freg = reader.read_u64()?;
+        }
+
+        self.pc = reader.read_u32()?;
+        self.next_pc = self.pc;
+        self.privilege_level = PrivilegeLevel::from_bits(reader.read_u8()? as u32).ok_or(SnapshotError::Corrupted)?;
+        self.waiting_for_interrupt = reader.read_bool()?;
+
+        self.control_and_status.load_state(reader)?;
+        self.tlb.flush_all();
+        self.flush_block_cache();
+
+        Ok(())
+    }
+
+    // Alineamiento de las instrucciones, 2 bytes si esta activa la extension C
+    pub fn get_ialign(&self) -> u32 {
+        if self.control_and_status.read_misa().get_c() {
+            2
+        } else {
+            4
+        }
+    }
+
+    // Devuelve la interrupcion de mayor prioridad que se puede atender en el
+    // nivel de privilegio actual
+    pub fn get_pending_interrupt(&self) -> Option<Interrupt> {
+        let pending = self.control_and_status.read_enabled_interrupts();
+
+        if pending == 0 {
+            return None;
+        }
+
+        let mstatus = self.control_and_status.read_mstatus_unchecked();
+        let mideleg = self.control_and_status.read_csr(MIDELEG, PrivilegeLevel::Machine).unwrap();
+
+        // Las interrupciones no delegadas se atienden en M, las delegadas en S
+        let machine_enabled = self.privilege_level < PrivilegeLevel::Machine || mstatus.get_mie();
+        let supervisor_enabled = self.privilege_level < PrivilegeLevel::Supervisor
+            || (self.privilege_level == PrivilegeLevel::Supervisor && mstatus.get_sie());
+
+        let machine_pending = pending & !mideleg;
+        let supervisor_pending = pending & mideleg;
+
+        INTERRUPT_PRIORITY
+            .iter()
+            .find(|int_type| {
+                let bit = 1 << (**int_type as u32);
+
+                (machine_enabled && machine_pending & bit != 0) || (supervisor_enabled && supervisor_pending & bit != 0)
+            })
+            .map(|int_type| Interrupt::new(*int_type, 0))
+    }
 }
 
 fn get_i_imm_val(instr: u32) -> u32 {
@@ -444,10 +717,11 @@ pub enum InstructionType {
     J(JInstruction),
     U(UInstruction),
     Atomic(AtomicInstruction),
+    F(FInstruction),
 }
 
 impl InstructionType {
-    pub fn execute(&mut self, mmu: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    pub fn execute(&self, mmu: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
         match self {
             Self::R(instr) => instr.execute(core),
             Self::I(instr) => instr.execute(mmu, core),
@@ -456,6 +730,7 @@ impl InstructionType {
             Self::J(instr) => instr.execute(core),
             Self::U(instr) => instr.execute(core),
             Self::Atomic(instr) => instr.execute(mmu, core),
+            Self::F(instr) => instr.execute(mmu, core),
         }
     }
 }
@@ -483,7 +758,7 @@ impl RInstruction {
         }
     }
 
-    fn execute(&mut self, core: &mut RVCore) -> Result<(), Exception> {
+    fn execute(&self, core: &mut RVCore) -> Result<(), Exception> {
         (self.function)(self, core)
     }
 }
@@ -515,7 +790,7 @@ impl IInstruction {
         }
     }
 
-    fn execute(&mut self, mmu: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    fn execute(&self, mmu: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
         (self.function)(self, mmu, core)
     }
 }
@@ -543,7 +818,7 @@ impl SInstruction {
         }
     }
 
-    pub fn execute(&mut self, mmu: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    pub fn execute(&self, mmu: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
         (self.function)(self, mmu, core)
     }
 }
@@ -571,7 +846,7 @@ impl BInstruction {
         }
     }
 
-    pub fn execute(&mut self, core: &mut RVCore) -> Result<(), Exception> {
+    pub fn execute(&self, core: &mut RVCore) -> Result<(), Exception> {
         (self.function)(self, core)
     }
 }
@@ -592,7 +867,7 @@ impl JInstruction {
         Self { imm, rd, function }
     }
 
-    pub fn execute(&mut self, core: &mut RVCore) -> Result<(), Exception> {
+    pub fn execute(&self, core: &mut RVCore) -> Result<(), Exception> {
         (self.function)(self, core)
     }
 }
@@ -614,7 +889,7 @@ impl UInstruction {
         Self { imm, rd, function }
     }
 
-    pub fn execute(&mut self, core: &mut RVCore) -> Result<(), Exception> {
+    pub fn execute(&self, core: &mut RVCore) -> Result<(), Exception> {
         (self.function)(self, core)
     }
 }
@@ -646,13 +921,51 @@ impl AtomicInstruction {
         }
     }
 
-    pub fn execute(&mut self, mmu: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+    pub fn execute(&self, mmu: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
         // (self.function)(self.rs1, self.rs2, self.rd, self.mmu, self.core)
         (self.function)(self, mmu, core)
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq)]
+// Instrucciones de coma flotante. Todos los campos estan siempre en la misma
+// posicion, asi que se sacan directamente de la instruccion
+pub struct FInstruction {
+    pub rs1: u32,
+    pub rs2: u32,
+    pub rs3: u32,
+    pub rd: u32,
+    pub rm: u32,
+    pub imm: u32,
+
+    pub data: u32,
+
+    function: fn(&Self, &mut Bus, &mut RVCore) -> Result<(), Exception>,
+}
+
+impl FInstruction {
+    pub fn new(
+        data: u32,
+        imm: u32,
+        function: fn(&Self, &mut Bus, &mut RVCore) -> Result<(), Exception>,
+    ) -> Self {
+        Self {
+            rs1: (data >> 15) & 0x1F,
+            rs2: (data >> 20) & 0x1F,
+            rs3: (data >> 27) & 0x1F,
+            rd: (data >> 7) & 0x1F,
+            rm: (data >> 12) & 0x7,
+            imm,
+            data,
+            function,
+        }
+    }
+
+    pub fn execute(&self, mmu: &mut Bus, core: &mut RVCore) -> Result<(), Exception> {
+        (self.function)(self, mmu, core)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
 pub enum PrivilegeLevel {
     User = 0x00,
     Supervisor = 0x01,
@@ -662,11 +975,16 @@ pub enum PrivilegeLevel {
 
 impl PrivilegeLevel {
     pub fn new(val: u32) -> Self {
+        Self::from_bits(val).unwrap_or(Self::Machine) // TODO
+    }
+
+    // None para el nivel reservado (2) y cualquier otro valor
+    pub fn from_bits(val: u32) -> Option<Self> {
         match val {
-            0x00 => Self::User,
-            0x01 => Self::Supervisor,
-            0x03 => Self::Machine,
-            _ => Self::Machine, // TODO
+            0x00 => Some(Self::User),
+            0x01 => Some(Self::Supervisor),
+            0x03 => Some(Self::Machine),
+            _ => None,
         }
     }
 }
@@ -676,7 +994,7 @@ trait Trapable {
     fn get_val(&self) -> u32;
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ExceptionType {
     InstructionAddressMisaligned = 0,
     InstructionAccessFault = 1,
@@ -697,7 +1015,7 @@ pub enum ExceptionType {
     HardwareError = 19,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Exception {
     exc_type: ExceptionType,
     val: u32
@@ -719,6 +1037,14 @@ impl Exception {
             exc_type, val
         }
     }
+
+    pub fn get_type(&self) -> ExceptionType {
+        self.exc_type
+    }
+
+    pub fn get_tval(&self) -> u32 {
+        self.val
+    }
 }
 
 pub trait WithVal<T> {
@@ -751,6 +1077,23 @@ pub enum InterruptType {
     CounterOverflowInt = 13,
 }
 
+// Orden de prioridad de las interrupciones, de mayor a menor
+const INTERRUPT_PRIORITY: [InterruptType; 7] = [
+    InterruptType::MachineExternalInt,
+    InterruptType::MachineSwInt,
+    InterruptType::MachineTimerInt,
+    InterruptType::SupervisorExternalInt,
+    InterruptType::SupervisorSwInt,
+    InterruptType::SupervisorTimerInt,
+    InterruptType::CounterOverflowInt,
+];
+
+impl Interrupt {
+    pub fn new(int_type: InterruptType, val: u32) -> Self {
+        Self { int_type, val }
+    }
+}
+
 impl Trapable for Interrupt {
     fn get_cause(&self) -> u32 {
         self.int_type as u32
@@ -803,15 +1146,7 @@ impl Trap {
         core.control_and_status.write_csr(MSTATUS, core.privilege_level, mstatus.0).unwrap();
 
         let mtvec = core.control_and_status.read_csr(MTVEC, core.privilege_level).unwrap();
-        let base = mtvec & 0xFFFFFFFC;
-
-        // TODO Esto es para INTS (Creo)
-        // if mtvec & 0b11 == 0 {
-        //     core.pc = base;
-        // } else {
-        //     core.pc = base + 4 * cause;
-        // }
-        core.pc = base;
+        core.pc = self.get_trap_vector(mtvec, cause);
     }
 
     fn handle_supervisor_trap(&self, core: &mut RVCore, cause: u32) {
@@ -832,21 +1167,31 @@ impl Trap {
         core.control_and_status.write_csr(SSTATUS, core.privilege_level, sstatus.0).unwrap();
 
         let stvec = core.control_and_status.read_csr(STVEC, core.privilege_level).unwrap();
-        let base = stvec & 0xFFFFFFFC;
-        
-        // TODO Esto es para INTS (Creo)
-        // if mtvec & 0b11 == 0 {
-        //     core.pc = base;
-        // } else {
-        //     core.pc = base + 4 * cause;
-        // }
-        core.pc = base;
+        core.pc = self.get_trap_vector(stvec, cause);
+    }
+
+    // En modo vectorizado solo las interrupciones saltan a base + 4 * causa,
+    // las excepciones siempre van a base
+    fn get_trap_vector(&self, tvec: u32, cause: u32) -> u32 {
+        let base = tvec & TVEC_BASE_MASK;
+
+        match self {
+            Self::Interrupt(_) if tvec & TVEC_MODE_MASK == TVEC_MODE_VECTORED => {
+                base.wrapping_add(4 * (cause & !(1 << 31)))
+            }
+            _ => base,
+        }
     }
 
     pub fn handle(&self, core: &mut RVCore) {
         let prev_priv_level = core.privilege_level;
         let mut cause = self.get_cause();
 
+        core.count_event(match self {
+            Self::Exception(_) => HpmEvent::Exception,
+            Self::Interrupt(_) => HpmEvent::Interrupt,
+        });
+
         let delegated = match self {
             Self::Exception(_) => {
                 let medelegl = core.control_and_status.read_csr(MEDELEG, PrivilegeLevel::Machine).unwrap();
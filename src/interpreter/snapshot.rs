@@ -0,0 +1,253 @@
+// Formato binario de las instantaneas del estado de la maquina.
+//
+// Empieza por SNAPSHOT_MAGIC y la version del formato, seguidos del estado de
+// cada componente en el orden en que lo escribe Interpreter::save_snapshot.
+// Todos los enteros se guardan en little endian. Si cambia lo que se guarda de
+// algun componente hay que subir SNAPSHOT_VERSION.
+
+use std::{fmt, io};
+
+pub const SNAPSHOT_MAGIC: [u8; 8] = *b"RVEMUSNP";
+pub const SNAPSHOT_VERSION: u32 = 4;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    InvalidMagic,
+    UnsupportedVersion(u32),
+    Truncated,
+    Corrupted,
+    // La instantanea es de una maquina con otra configuracion
+    Mismatch { field: &'static str, expected: u64, found: u64 },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::InvalidMagic => write!(f, "no es una instantanea"),
+            Self::UnsupportedVersion(version) => write!(f, "version de instantanea no soportada ({version})"),
+            Self::Truncated => write!(f, "instantanea truncada"),
+            Self::Corrupted => write!(f, "instantanea corrupta"),
+            Self::Mismatch { field, expected, found } => {
+                write!(f, "{field} no coincide: se esperaba {expected} y la instantanea tiene {found}")
+            }
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+#[derive(Default)]
+pub struct SnapshotWriter {
+    buf: Vec<u8>,
+}
+
+impl SnapshotWriter {
+    pub fn new() -> Self {
+        let mut writer = Self::default();
+        writer.write_bytes(&SNAPSHOT_MAGIC);
+        writer.write_u32(SNAPSHOT_VERSION);
+
+        writer
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub fn write_u8(&mut self, val: u8) {
+        self.buf.push(val);
+    }
+
+    pub fn write_bool(&mut self, val: bool) {
+        self.write_u8(val as u8);
+    }
+
+    pub fn write_u32(&mut self, val: u32) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, val: u64) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+}
+
+pub struct SnapshotReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> SnapshotReader<'a> {
+    // Comprueba la cabecera y deja el lector al principio del estado
+    pub fn new(data: &'a [u8]) -> Result<Self, SnapshotError> {
+        let mut reader = Self { data };
+
+        if reader.read_bytes(SNAPSHOT_MAGIC.len()).map_err(|_| SnapshotError::InvalidMagic)? != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::InvalidMagic);
+        }
+
+        let version = reader.read_u32()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        Ok(reader)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.data.len() < len {
+            return Err(SnapshotError::Truncated);
+        }
+
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+
+        Ok(bytes)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, SnapshotError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(SnapshotError::Corrupted),
+        }
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.read_bytes(8)?.try_into().unwrap()))
+    }
+
+    // Lee un valor que tiene que coincidir con la configuracion actual
+    pub fn expect_u32(&mut self, field: &'static str, expected: u32) -> Result<(), SnapshotError> {
+        let found = self.read_u32()?;
+
+        if found != expected {
+            return Err(SnapshotError::Mismatch { field, expected: expected as u64, found: found as u64 });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::{SNAPSHOT_MAGIC, SNAPSHOT_VERSION, SnapshotError};
+    use crate::interpreter::{ExitStatus, Interpreter};
+
+    const PROGRAM: &str = "rv_tests/rv32uf-p-f_extra.hex";
+
+    fn interpreter() -> Interpreter {
+        let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+        interpreter.load_hex(PROGRAM).unwrap();
+
+        interpreter
+    }
+
+    fn snapshot_path(name: &str) -> String {
+        let path = env::temp_dir().join(format!("riscv_emu_{name}_{}.snap", process::id()));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn registers(interpreter: &Interpreter) -> (Vec<u32>, Vec<u64>) {
+        let core = interpreter.core();
+
+        ((0..32).map(|reg| core.read_reg(reg)).collect(), (0..32).map(|reg| core.read_freg(reg)).collect())
+    }
+
+    #[test]
+    fn restored_snapshot_resumes_identically() {
+        let mut reference = interpreter();
+        let status = reference.run();
+        assert_eq!(status, ExitStatus::Halted { exit_code: 0 });
+
+        let mut original = interpreter();
+        assert_eq!(original.run_for(500), ExitStatus::InstructionLimit);
+
+        let path = snapshot_path("round_trip");
+        original.save_snapshot(&path).unwrap();
+
+        // La maquina nueva no tiene el programa cargado, todo sale de la instantanea
+        let mut restored = Interpreter::with_htif(0x80001000, 0x80001040);
+        let result = restored.load_snapshot(&path);
+        fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        assert_eq!(restored.get_pc(), original.get_pc());
+        assert_eq!(registers(&restored), registers(&original));
+
+        assert_eq!(restored.run(), status);
+        assert_eq!(restored.get_pc(), reference.get_pc());
+        assert_eq!(registers(&restored), registers(&reference));
+    }
+
+    #[test]
+    fn rejects_bad_magic_and_version() {
+        let path = snapshot_path("bad_header");
+        interpreter().save_snapshot(&path).unwrap();
+        let data = fs::read(&path).unwrap();
+
+        let mut bad_version = data.clone();
+        let version = SNAPSHOT_MAGIC.len();
+        bad_version[version..version + 4].copy_from_slice(&(SNAPSHOT_VERSION + 1).to_le_bytes());
+        fs::write(&path, bad_version).unwrap();
+        let version_result = interpreter().load_snapshot(&path);
+
+        let mut bad_magic = data;
+        bad_magic[0] ^= 0xFF;
+        fs::write(&path, bad_magic).unwrap();
+        let magic_result = interpreter().load_snapshot(&path);
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(version_result, Err(SnapshotError::UnsupportedVersion(version)) if version == SNAPSHOT_VERSION + 1));
+        assert!(matches!(magic_result, Err(SnapshotError::InvalidMagic)));
+    }
+
+    #[test]
+    fn rejects_invalid_privilege_and_flags() {
+        let path = snapshot_path("bad_fields");
+        interpreter().save_snapshot(&path).unwrap();
+        let data = fs::read(&path).unwrap();
+
+        // Cabecera, numero de harts, hart actual, quantum, mhartid, x, f y pc
+        let privilege = SNAPSHOT_MAGIC.len() + 4 + 4 + 4 + 8 + 4 + 32 * 4 + 32 * 8 + 4;
+        let waiting_for_interrupt = privilege + 1;
+        assert_eq!((data[privilege], data[waiting_for_interrupt]), (3, 0));
+
+        let mut results = Vec::new();
+        for (offset, val) in [(privilege, 2), (privilege, 7), (waiting_for_interrupt, 2)] {
+            let mut bad = data.clone();
+            bad[offset] = val;
+            fs::write(&path, bad).unwrap();
+            results.push(interpreter().load_snapshot(&path));
+        }
+
+        fs::remove_file(&path).unwrap();
+
+        for result in results {
+            assert!(matches!(result, Err(SnapshotError::Corrupted)));
+        }
+    }
+}
@@ -0,0 +1,625 @@
+// Aritmetica IEEE-754 en software para las extensiones F y D.
+//
+// Los valores se manejan siempre como bits (u64) y cada operacion devuelve el
+// resultado junto con los flags de excepcion acumulables en fflags. No se usa
+// la FPU del host para poder soportar todos los modos de redondeo y detectar
+// underflow despues de redondear, como pide la especificacion de RISC-V.
+
+pub const FLAG_NX: u32 = 1 << 0;
+pub const FLAG_UF: u32 = 1 << 1;
+pub const FLAG_OF: u32 = 1 << 2;
+pub const FLAG_DZ: u32 = 1 << 3;
+pub const FLAG_NV: u32 = 1 << 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RoundingMode {
+    NearestEven = 0,
+    Zero = 1,
+    Down = 2,
+    Up = 3,
+    NearestMaxMagnitude = 4,
+}
+
+impl RoundingMode {
+    pub fn new(val: u32) -> Option<Self> {
+        match val {
+            0 => Some(Self::NearestEven),
+            1 => Some(Self::Zero),
+            2 => Some(Self::Down),
+            3 => Some(Self::Up),
+            4 => Some(Self::NearestMaxMagnitude),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FloatFormat {
+    Single,
+    Double,
+}
+
+impl FloatFormat {
+    const fn exp_bits(self) -> u32 {
+        match self {
+            Self::Single => 8,
+            Self::Double => 11,
+        }
+    }
+
+    const fn frac_bits(self) -> u32 {
+        match self {
+            Self::Single => 23,
+            Self::Double => 52,
+        }
+    }
+
+    const fn bias(self) -> i32 {
+        (1 << (self.exp_bits() - 1)) - 1
+    }
+
+    const fn max_exp_field(self) -> u64 {
+        (1 << self.exp_bits()) - 1
+    }
+
+    const fn sign_bit(self, sign: bool) -> u64 {
+        (sign as u64) << (self.exp_bits() + self.frac_bits())
+    }
+
+    pub const fn sign_mask(self) -> u64 {
+        self.sign_bit(true)
+    }
+
+    pub const fn canonical_nan(self) -> u64 {
+        match self {
+            Self::Single => 0x7FC00000,
+            Self::Double => 0x7FF8000000000000,
+        }
+    }
+
+    fn zero(self, sign: bool) -> u64 {
+        self.sign_bit(sign)
+    }
+
+    fn inf(self, sign: bool) -> u64 {
+        self.sign_bit(sign) | (self.max_exp_field() << self.frac_bits())
+    }
+
+    fn max_finite(self, sign: bool) -> u64 {
+        self.inf(sign) - 1
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Unpacked {
+    Zero { sign: bool },
+    // value = sig * 2^exp
+    Finite { sign: bool, exp: i32, sig: u128 },
+    Inf { sign: bool },
+    NaN { signaling: bool },
+}
+
+impl Unpacked {
+    fn is_nan(&self) -> bool {
+        matches!(self, Self::NaN { .. })
+    }
+
+    fn is_signaling(&self) -> bool {
+        matches!(self, Self::NaN { signaling: true })
+    }
+}
+
+fn unpack(fmt: FloatFormat, bits: u64) -> Unpacked {
+    let frac_bits = fmt.frac_bits();
+
+    let sign = (bits >> (fmt.exp_bits() + frac_bits)) & 1 == 1;
+    let exp_field = (bits >> frac_bits) & fmt.max_exp_field();
+    let frac = bits & ((1 << frac_bits) - 1);
+
+    if exp_field == fmt.max_exp_field() {
+        if frac == 0 {
+            Unpacked::Inf { sign }
+        } else {
+            Unpacked::NaN {
+                signaling: (frac >> (frac_bits - 1)) & 1 == 0,
+            }
+        }
+    } else if exp_field == 0 {
+        if frac == 0 {
+            Unpacked::Zero { sign }
+        } else {
+            Unpacked::Finite {
+                sign,
+                exp: 1 - fmt.bias() - frac_bits as i32,
+                sig: frac as u128,
+            }
+        }
+    } else {
+        Unpacked::Finite {
+            sign,
+            exp: exp_field as i32 - fmt.bias() - frac_bits as i32,
+            sig: (frac | (1 << frac_bits)) as u128,
+        }
+    }
+}
+
+fn invalid_if_signaling(values: &[Unpacked]) -> u32 {
+    if values.iter().any(Unpacked::is_signaling) {
+        FLAG_NV
+    } else {
+        0
+    }
+}
+
+fn shift_right_jam(val: u128, shift: u32) -> u128 {
+    if shift == 0 {
+        val
+    } else if shift >= 128 {
+        (val != 0) as u128
+    } else {
+        (val >> shift) | ((val & ((1 << shift) - 1)) != 0) as u128
+    }
+}
+
+// Redondea sig >> shift segun el modo. Devuelve el valor redondeado y si se
+// ha perdido precision.
+fn round_shifted(sig: u128, shift: u32, sign: bool, rm: RoundingMode) -> (u128, bool) {
+    let (quotient, above_half, half, inexact) = if shift == 0 {
+        (sig, false, false, false)
+    } else if shift > 128 {
+        (0, false, false, sig != 0)
+    } else {
+        let quotient = if shift == 128 { 0 } else { sig >> shift };
+        let rem = if shift == 128 { sig } else { sig & ((1 << shift) - 1) };
+        let half_val = 1u128 << (shift - 1);
+
+        (quotient, rem > half_val, rem == half_val, rem != 0)
+    };
+
+    let increment = match rm {
+        RoundingMode::NearestEven => above_half || (half && quotient & 1 == 1),
+        RoundingMode::NearestMaxMagnitude => above_half || half,
+        RoundingMode::Zero => false,
+        RoundingMode::Down => sign && inexact,
+        RoundingMode::Up => !sign && inexact,
+    };
+
+    (quotient + increment as u128, inexact)
+}
+
+fn round_pack(fmt: FloatFormat, sign: bool, exp: i32, sig: u128, rm: RoundingMode) -> (u64, u32) {
+    if sig == 0 {
+        return (fmt.zero(sign), 0);
+    }
+
+    let precision = fmt.frac_bits() + 1;
+    let emin = 1 - fmt.bias();
+
+    // Normaliza para tener el bit mas significativo en la posicion 127
+    let leading_zeros = sig.leading_zeros();
+    let sig = sig << leading_zeros;
+    let mut msb_exp = exp - leading_zeros as i32 + 127;
+    let mut shift = 128 - precision;
+
+    // La deteccion de tininess se hace despues de redondear con rango de
+    // exponente ilimitado
+    let tiny = if msb_exp < emin - 1 {
+        true
+    } else if msb_exp == emin - 1 {
+        let (rounded, _) = round_shifted(sig, shift, sign, rm);
+        rounded >> precision == 0
+    } else {
+        false
+    };
+
+    if msb_exp < emin {
+        shift += (emin - msb_exp) as u32;
+        msb_exp = emin;
+    }
+
+    let (rounded, inexact) = round_shifted(sig, shift, sign, rm);
+
+    // Si el redondeo desborda la mantisa el acarreo incrementa el exponente
+    let bits = (((msb_exp + fmt.bias() - 1) as u64) << fmt.frac_bits()) + rounded as u64;
+
+    if bits >> fmt.frac_bits() >= fmt.max_exp_field() {
+        let to_inf = match rm {
+            RoundingMode::NearestEven | RoundingMode::NearestMaxMagnitude => true,
+            RoundingMode::Zero => false,
+            RoundingMode::Down => sign,
+            RoundingMode::Up => !sign,
+        };
+
+        let val = if to_inf { fmt.inf(sign) } else { fmt.max_finite(sign) };
+        return (val, FLAG_OF | FLAG_NX);
+    }
+
+    let mut flags = 0;
+    if inexact {
+        flags |= FLAG_NX;
+        if tiny {
+            flags |= FLAG_UF;
+        }
+    }
+
+    (fmt.sign_bit(sign) | bits, flags)
+}
+
+fn add_finite(
+    fmt: FloatFormat,
+    (sign_a, exp_a, sig_a): (bool, i32, u128),
+    (sign_b, exp_b, sig_b): (bool, i32, u128),
+    rm: RoundingMode,
+) -> (u64, u32) {
+    // Se deja el bit mas significativo en la posicion 125 para que la suma
+    // no desborde
+    let shift_a = sig_a.leading_zeros() - 2;
+    let shift_b = sig_b.leading_zeros() - 2;
+    let (mut sig_a, mut exp_a) = (sig_a << shift_a, exp_a - shift_a as i32);
+    let (mut sig_b, mut exp_b) = (sig_b << shift_b, exp_b - shift_b as i32);
+
+    if exp_a >= exp_b {
+        sig_b = shift_right_jam(sig_b, (exp_a - exp_b) as u32);
+        exp_b = exp_a;
+    } else {
+        sig_a = shift_right_jam(sig_a, (exp_b - exp_a) as u32);
+        exp_a = exp_b;
+    }
+
+    if sign_a == sign_b {
+        round_pack(fmt, sign_a, exp_a, sig_a + sig_b, rm)
+    } else if sig_a > sig_b {
+        round_pack(fmt, sign_a, exp_a, sig_a - sig_b, rm)
+    } else if sig_b > sig_a {
+        round_pack(fmt, sign_b, exp_b, sig_b - sig_a, rm)
+    } else {
+        (fmt.zero(rm == RoundingMode::Down), 0)
+    }
+}
+
+pub fn add(fmt: FloatFormat, a: u64, b: u64, rm: RoundingMode) -> (u64, u32) {
+    let (ua, ub) = (unpack(fmt, a), unpack(fmt, b));
+
+    match (ua, ub) {
+        _ if ua.is_nan() || ub.is_nan() => (fmt.canonical_nan(), invalid_if_signaling(&[ua, ub])),
+        (Unpacked::Inf { sign: sign_a }, Unpacked::Inf { sign: sign_b }) => {
+            if sign_a == sign_b {
+                (fmt.inf(sign_a), 0)
+            } else {
+                (fmt.canonical_nan(), FLAG_NV)
+            }
+        }
+        (Unpacked::Inf { sign }, _) | (_, Unpacked::Inf { sign }) => (fmt.inf(sign), 0),
+        (Unpacked::Zero { sign: sign_a }, Unpacked::Zero { sign: sign_b }) => {
+            if sign_a == sign_b {
+                (fmt.zero(sign_a), 0)
+            } else {
+                (fmt.zero(rm == RoundingMode::Down), 0)
+            }
+        }
+        (Unpacked::Zero { .. }, _) => (b, 0),
+        (_, Unpacked::Zero { .. }) => (a, 0),
+        (
+            Unpacked::Finite { sign: sign_a, exp: exp_a, sig: sig_a },
+            Unpacked::Finite { sign: sign_b, exp: exp_b, sig: sig_b },
+        ) => add_finite(fmt, (sign_a, exp_a, sig_a), (sign_b, exp_b, sig_b), rm),
+        _ => unreachable!(),
+    }
+}
+
+pub fn sub(fmt: FloatFormat, a: u64, b: u64, rm: RoundingMode) -> (u64, u32) {
+    add(fmt, a, b ^ fmt.sign_bit(true), rm)
+}
+
+pub fn mul(fmt: FloatFormat, a: u64, b: u64, rm: RoundingMode) -> (u64, u32) {
+    let (ua, ub) = (unpack(fmt, a), unpack(fmt, b));
+
+    match (ua, ub) {
+        _ if ua.is_nan() || ub.is_nan() => (fmt.canonical_nan(), invalid_if_signaling(&[ua, ub])),
+        (Unpacked::Inf { .. }, Unpacked::Zero { .. }) | (Unpacked::Zero { .. }, Unpacked::Inf { .. }) => {
+            (fmt.canonical_nan(), FLAG_NV)
+        }
+        (Unpacked::Inf { sign: sign_a }, Unpacked::Inf { sign: sign_b })
+        | (Unpacked::Inf { sign: sign_a }, Unpacked::Finite { sign: sign_b, .. })
+        | (Unpacked::Finite { sign: sign_a, .. }, Unpacked::Inf { sign: sign_b }) => (fmt.inf(sign_a ^ sign_b), 0),
+        (Unpacked::Zero { sign: sign_a }, Unpacked::Zero { sign: sign_b })
+        | (Unpacked::Zero { sign: sign_a }, Unpacked::Finite { sign: sign_b, .. })
+        | (Unpacked::Finite { sign: sign_a, .. }, Unpacked::Zero { sign: sign_b }) => (fmt.zero(sign_a ^ sign_b), 0),
+        (
+            Unpacked::Finite { sign: sign_a, exp: exp_a, sig: sig_a },
+            Unpacked::Finite { sign: sign_b, exp: exp_b, sig: sig_b },
+        ) => round_pack(fmt, sign_a ^ sign_b, exp_a + exp_b, sig_a * sig_b, rm),
+        _ => unreachable!(),
+    }
+}
+
+pub fn div(fmt: FloatFormat, a: u64, b: u64, rm: RoundingMode) -> (u64, u32) {
+    let (ua, ub) = (unpack(fmt, a), unpack(fmt, b));
+
+    match (ua, ub) {
+        _ if ua.is_nan() || ub.is_nan() => (fmt.canonical_nan(), invalid_if_signaling(&[ua, ub])),
+        (Unpacked::Inf { .. }, Unpacked::Inf { .. }) | (Unpacked::Zero { .. }, Unpacked::Zero { .. }) => {
+            (fmt.canonical_nan(), FLAG_NV)
+        }
+        (Unpacked::Inf { sign: sign_a }, Unpacked::Zero { sign: sign_b })
+        | (Unpacked::Inf { sign: sign_a }, Unpacked::Finite { sign: sign_b, .. }) => (fmt.inf(sign_a ^ sign_b), 0),
+        (Unpacked::Finite { sign: sign_a, .. }, Unpacked::Zero { sign: sign_b }) => {
+            (fmt.inf(sign_a ^ sign_b), FLAG_DZ)
+        }
+        (Unpacked::Zero { sign: sign_a }, Unpacked::Inf { sign: sign_b })
+        | (Unpacked::Zero { sign: sign_a }, Unpacked::Finite { sign: sign_b, .. })
+        | (Unpacked::Finite { sign: sign_a, .. }, Unpacked::Inf { sign: sign_b }) => (fmt.zero(sign_a ^ sign_b), 0),
+        (
+            Unpacked::Finite { sign: sign_a, exp: exp_a, sig: sig_a },
+            Unpacked::Finite { sign: sign_b, exp: exp_b, sig: sig_b },
+        ) => {
+            // Ambas mantisas con el bit mas significativo en la posicion 63,
+            // el cociente tiene al menos 62 bits
+            let shift_a = sig_a.leading_zeros() - 64;
+            let shift_b = sig_b.leading_zeros() - 64;
+            let sig_a = sig_a << shift_a;
+            let sig_b = sig_b << shift_b;
+
+            let num = sig_a << 62;
+            let quotient = (num / sig_b) | ((num % sig_b != 0) as u128);
+            let exp = (exp_a - shift_a as i32) - (exp_b - shift_b as i32) - 62;
+
+            round_pack(fmt, sign_a ^ sign_b, exp, quotient, rm)
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn isqrt(val: u128) -> (u128, bool) {
+    let mut rem = val;
+    let mut root = 0u128;
+    let mut bit = 1u128 << 126;
+
+    while bit > val {
+        bit >>= 2;
+    }
+
+    while bit != 0 {
+        if rem >= root + bit {
+            rem -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+
+    (root, rem != 0)
+}
+
+pub fn sqrt(fmt: FloatFormat, a: u64, rm: RoundingMode) -> (u64, u32) {
+    match unpack(fmt, a) {
+        ua @ Unpacked::NaN { .. } => (fmt.canonical_nan(), invalid_if_signaling(&[ua])),
+        Unpacked::Zero { sign } => (fmt.zero(sign), 0),
+        Unpacked::Inf { sign: false } => (fmt.inf(false), 0),
+        Unpacked::Inf { sign: true } | Unpacked::Finite { sign: true, .. } => (fmt.canonical_nan(), FLAG_NV),
+        Unpacked::Finite { sign: false, exp, sig } => {
+            let shift = sig.leading_zeros() - 64;
+            let mut sig = sig << shift;
+            let mut exp = exp - shift as i32;
+
+            // El exponente tiene que ser par para poder dividirlo entre 2
+            if exp & 1 != 0 {
+                sig <<= 1;
+                exp -= 1;
+            }
+
+            let (root, inexact) = isqrt(sig << 60);
+
+            round_pack(fmt, false, (exp - 60) / 2, root | inexact as u128, rm)
+        }
+    }
+}
+
+// a * b + c con un unico redondeo
+pub fn fused_mul_add(fmt: FloatFormat, a: u64, b: u64, c: u64, rm: RoundingMode) -> (u64, u32) {
+    let (ua, ub, uc) = (unpack(fmt, a), unpack(fmt, b), unpack(fmt, c));
+
+    let inf_times_zero = matches!(
+        (ua, ub),
+        (Unpacked::Inf { .. }, Unpacked::Zero { .. }) | (Unpacked::Zero { .. }, Unpacked::Inf { .. })
+    );
+
+    // inf * 0 es invalido incluso si el sumando es un qNaN
+    if inf_times_zero {
+        return (fmt.canonical_nan(), FLAG_NV);
+    }
+
+    if ua.is_nan() || ub.is_nan() || uc.is_nan() {
+        return (fmt.canonical_nan(), invalid_if_signaling(&[ua, ub, uc]));
+    }
+
+    let product_sign = match (ua, ub) {
+        (
+            Unpacked::Zero { sign: sign_a } | Unpacked::Finite { sign: sign_a, .. } | Unpacked::Inf { sign: sign_a },
+            Unpacked::Zero { sign: sign_b } | Unpacked::Finite { sign: sign_b, .. } | Unpacked::Inf { sign: sign_b },
+        ) => sign_a ^ sign_b,
+        _ => unreachable!(),
+    };
+
+    match (ua, ub, uc) {
+        (Unpacked::Inf { .. }, _, _) | (_, Unpacked::Inf { .. }, _) => match uc {
+            Unpacked::Inf { sign } if sign != product_sign => (fmt.canonical_nan(), FLAG_NV),
+            _ => (fmt.inf(product_sign), 0),
+        },
+        (_, _, Unpacked::Inf { sign }) => (fmt.inf(sign), 0),
+        (Unpacked::Zero { .. }, _, _) | (_, Unpacked::Zero { .. }, _) => match uc {
+            Unpacked::Zero { sign } if sign == product_sign => (fmt.zero(sign), 0),
+            Unpacked::Zero { .. } => (fmt.zero(rm == RoundingMode::Down), 0),
+            _ => (c, 0),
+        },
+        (
+            Unpacked::Finite { exp: exp_a, sig: sig_a, .. },
+            Unpacked::Finite { exp: exp_b, sig: sig_b, .. },
+            Unpacked::Zero { .. },
+        ) => round_pack(fmt, product_sign, exp_a + exp_b, sig_a * sig_b, rm),
+        (
+            Unpacked::Finite { exp: exp_a, sig: sig_a, .. },
+            Unpacked::Finite { exp: exp_b, sig: sig_b, .. },
+            Unpacked::Finite { sign: sign_c, exp: exp_c, sig: sig_c },
+        ) => add_finite(
+            fmt,
+            (product_sign, exp_a + exp_b, sig_a * sig_b),
+            (sign_c, exp_c, sig_c),
+            rm,
+        ),
+        _ => unreachable!(),
+    }
+}
+
+// Orden total sin NaNs, -0 es menor que +0
+fn total_lt(fmt: FloatFormat, a: u64, b: u64) -> bool {
+    let sign_a = a & fmt.sign_bit(true) != 0;
+    let sign_b = b & fmt.sign_bit(true) != 0;
+    let mag_a = a & !fmt.sign_bit(true);
+    let mag_b = b & !fmt.sign_bit(true);
+
+    match (sign_a, sign_b) {
+        (true, false) => true,
+        (false, true) => false,
+        (false, false) => mag_a < mag_b,
+        (true, true) => mag_a > mag_b,
+    }
+}
+
+fn min_max(fmt: FloatFormat, a: u64, b: u64, want_min: bool) -> (u64, u32) {
+    let (ua, ub) = (unpack(fmt, a), unpack(fmt, b));
+    let flags = invalid_if_signaling(&[ua, ub]);
+
+    let val = match (ua.is_nan(), ub.is_nan()) {
+        (true, true) => fmt.canonical_nan(),
+        (true, false) => b,
+        (false, true) => a,
+        (false, false) => {
+            if total_lt(fmt, a, b) == want_min {
+                a
+            } else {
+                b
+            }
+        }
+    };
+
+    (val, flags)
+}
+
+pub fn min(fmt: FloatFormat, a: u64, b: u64) -> (u64, u32) {
+    min_max(fmt, a, b, true)
+}
+
+pub fn max(fmt: FloatFormat, a: u64, b: u64) -> (u64, u32) {
+    min_max(fmt, a, b, false)
+}
+
+fn is_zero(fmt: FloatFormat, a: u64) -> bool {
+    a & !fmt.sign_bit(true) == 0
+}
+
+pub fn eq(fmt: FloatFormat, a: u64, b: u64) -> (bool, u32) {
+    let (ua, ub) = (unpack(fmt, a), unpack(fmt, b));
+
+    if ua.is_nan() || ub.is_nan() {
+        return (false, invalid_if_signaling(&[ua, ub]));
+    }
+
+    (a == b || (is_zero(fmt, a) && is_zero(fmt, b)), 0)
+}
+
+pub fn lt(fmt: FloatFormat, a: u64, b: u64) -> (bool, u32) {
+    if unpack(fmt, a).is_nan() || unpack(fmt, b).is_nan() {
+        return (false, FLAG_NV);
+    }
+
+    (!(is_zero(fmt, a) && is_zero(fmt, b)) && total_lt(fmt, a, b), 0)
+}
+
+pub fn le(fmt: FloatFormat, a: u64, b: u64) -> (bool, u32) {
+    if unpack(fmt, a).is_nan() || unpack(fmt, b).is_nan() {
+        return (false, FLAG_NV);
+    }
+
+    ((is_zero(fmt, a) && is_zero(fmt, b)) || a == b || total_lt(fmt, a, b), 0)
+}
+
+pub fn classify(fmt: FloatFormat, a: u64) -> u32 {
+    let subnormal = (a >> fmt.frac_bits()) & fmt.max_exp_field() == 0;
+
+    let class = match unpack(fmt, a) {
+        Unpacked::Inf { sign: true } => 0,
+        Unpacked::Finite { sign: true, .. } if !subnormal => 1,
+        Unpacked::Finite { sign: true, .. } => 2,
+        Unpacked::Zero { sign: true } => 3,
+        Unpacked::Zero { sign: false } => 4,
+        Unpacked::Finite { sign: false, .. } if subnormal => 5,
+        Unpacked::Finite { sign: false, .. } => 6,
+        Unpacked::Inf { sign: false } => 7,
+        Unpacked::NaN { signaling: true } => 8,
+        Unpacked::NaN { signaling: false } => 9,
+    };
+
+    1 << class
+}
+
+pub fn to_int(fmt: FloatFormat, a: u64, rm: RoundingMode, signed: bool) -> (u32, u32) {
+    let (max_val, min_val) = if signed {
+        (i32::MAX as u32, i32::MIN as u32)
+    } else {
+        (u32::MAX, 0)
+    };
+
+    match unpack(fmt, a) {
+        Unpacked::NaN { .. } => (max_val, FLAG_NV),
+        Unpacked::Inf { sign } => (if sign { min_val } else { max_val }, FLAG_NV),
+        Unpacked::Zero { .. } => (0, 0),
+        Unpacked::Finite { sign, exp, sig } => {
+            let (magnitude, inexact) = if exp >= 0 {
+                if exp > 64 {
+                    return (if sign { min_val } else { max_val }, FLAG_NV);
+                }
+
+                (sig << exp, false)
+            } else {
+                round_shifted(sig, (-exp) as u32, sign, rm)
+            };
+
+            let in_range = match (signed, sign) {
+                (true, false) => magnitude <= i32::MAX as u128,
+                (true, true) => magnitude <= 1 << 31,
+                (false, false) => magnitude <= u32::MAX as u128,
+                (false, true) => magnitude == 0,
+            };
+
+            if !in_range {
+                return (if sign { min_val } else { max_val }, FLAG_NV);
+            }
+
+            let val = if sign {
+                (magnitude as u32).wrapping_neg()
+            } else {
+                magnitude as u32
+            };
+
+            (val, if inexact { FLAG_NX } else { 0 })
+        }
+    }
+}
+
+pub fn from_int(fmt: FloatFormat, val: u32, rm: RoundingMode, signed: bool) -> (u64, u32) {
+    let sign = signed && (val as i32) < 0;
+    let magnitude = if sign { (val as i32).unsigned_abs() } else { val };
+
+    round_pack(fmt, sign, 0, magnitude as u128, rm)
+}
+
+pub fn convert(from: FloatFormat, to: FloatFormat, a: u64, rm: RoundingMode) -> (u64, u32) {
+    match unpack(from, a) {
+        ua @ Unpacked::NaN { .. } => (to.canonical_nan(), invalid_if_signaling(&[ua])),
+        Unpacked::Inf { sign } => (to.inf(sign), 0),
+        Unpacked::Zero { sign } => (to.zero(sign), 0),
+        Unpacked::Finite { sign, exp, sig } => round_pack(to, sign, exp, sig, rm),
+    }
+}
@@ -0,0 +1,244 @@
+// Traza de las instrucciones ejecutadas en el formato de `spike -l --log-commits`:
+//
+// core   0: 0x80000000 (0x00000297) auipc   t0, 0x0
+// core   0: 3 0x80000000 (0x00000297) x5  0x80000000
+// core   0: 3 0x8000000c (0x0182a283) x5  0x00000000 mem 0x80000018
+// core   0: 3 0x80000010 (0x00b2a023) mem 0x80000018 0x80000020
+//
+// Las instrucciones que provocan una excepcion no tienen linea de commit. En
+// su lugar se escribe la excepcion, y tval si spike lo muestra:
+//
+// core   0: 0x80000014 (0x00000000) unimp
+// core   0: exception trap_illegal_instruction, epc 0x80000014
+// core   0:           tval 0x00000000
+//
+// Las interrupciones no aparecen en la traza.
+//
+// Mientras la traza esta activa el core, los CSRs y el bus apuntan lo que
+// escribe cada instruccion. Si no lo esta solo se comprueba un Option.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use crate::interpreter::{
+    bus::Bus,
+    csr::get_csr_name,
+    disassembler::disassemble,
+    riscv_core::{Exception, ExceptionType, PrivilegeLevel, RVCore},
+    virtual_memory::sv32::AccessType,
+};
+
+pub struct MemAccess {
+    pub address: u32,
+    pub phys_address: u64,
+    pub access_type: AccessType,
+}
+
+// Escrituras de la instruccion en curso
+#[derive(Default)]
+pub struct CommitLog {
+    pub regs: Vec<(u32, u32)>,
+    pub fregs: Vec<(u32, u64)>,
+    pub mem: Vec<MemAccess>,
+}
+
+pub struct Tracer {
+    out: BufWriter<File>,
+}
+
+impl Tracer {
+    pub fn new(path: &str) -> io::Result<Self> {
+        Ok(Self {
+            out: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    // Empieza a apuntar las escrituras de la siguiente instruccion
+    pub fn begin(core: &mut RVCore, bus: &mut Bus) {
+        core.commit_log = Some(CommitLog::default());
+        core.control_and_status.written_csrs = Some(Vec::new());
+        bus.written_bytes = Some(Vec::new());
+    }
+
+    pub fn commit(
+        &mut self,
+        core: &mut RVCore,
+        bus: &mut Bus,
+        pc: u32,
+        priv_level: PrivilegeLevel,
+        instr: u32,
+        instr_len: u32,
+    ) -> io::Result<()> {
+        let log = core.commit_log.take().unwrap_or_default();
+        let csrs = core.control_and_status.written_csrs.take().unwrap_or_default();
+        let bytes = bus.written_bytes.take().unwrap_or_default();
+
+        let hart_id = core.get_hartid();
+        let encoding = encoding(instr, instr_len);
+
+        writeln!(self.out, "core {hart_id:3}: 0x{pc:08x} {encoding} {}", disassemble(instr))?;
+
+        let mut line = format!("core {hart_id:3}: {} 0x{pc:08x} {encoding}", priv_level as u32);
+
+        for (reg, val) in log.regs {
+            line += &format!(" x{reg:<2} 0x{val:08x}");
+        }
+
+        for (reg, val) in log.fregs {
+            line += &format!(" f{reg:<2} 0x{val:016x}");
+        }
+
+        for csr in csrs {
+            let val = core.control_and_status.read_csr(csr, PrivilegeLevel::Machine).unwrap_or_default();
+
+            line += &format!(" c{csr}_{} 0x{val:08x}", get_csr_name(csr).unwrap_or("unknown"));
+        }
+
+        for access in log.mem.iter().filter(|access| access.access_type == AccessType::Load) {
+            line += &format!(" mem 0x{:08x}", access.address);
+        }
+
+        for access in log.mem.iter().filter(|access| access.access_type == AccessType::StoreAmo) {
+            // Bytes escritos a partir de la direccion traducida
+            let written: Vec<u8> = (0..)
+                .map_while(|i| {
+                    bytes
+                        .iter()
+                        .find(|(address, _)| *address == access.phys_address + i)
+                        .map(|(_, byte)| *byte)
+                })
+                .take(8)
+                .collect();
+
+            // Un sc que falla no escribe nada
+            if written.is_empty() {
+                continue;
+            }
+
+            let val = written.iter().rev().fold(0u64, |val, byte| (val << 8) | *byte as u64);
+
+            line += &format!(" mem 0x{:08x} 0x{val:0width$x}", access.address, width = written.len() * 2);
+        }
+
+        writeln!(self.out, "{line}")
+    }
+
+    // instr es None si la excepcion se ha producido al leer la instruccion
+    pub fn trap(
+        &mut self,
+        core: &mut RVCore,
+        bus: &mut Bus,
+        pc: u32,
+        instr: Option<(u32, u32)>,
+        exception: Exception,
+    ) -> io::Result<()> {
+        core.commit_log = None;
+        core.control_and_status.written_csrs = None;
+        bus.written_bytes = None;
+
+        let hart_id = core.get_hartid();
+
+        if let Some((instr, instr_len)) = instr {
+            writeln!(self.out, "core {hart_id:3}: 0x{pc:08x} {} {}", encoding(instr, instr_len), disassemble(instr))?;
+        }
+
+        let (name, has_tval) = exception_name(exception.get_type());
+        writeln!(self.out, "core {hart_id:3}: exception {name}, epc 0x{pc:08x}")?;
+
+        if has_tval {
+            writeln!(self.out, "core {hart_id:3}:           tval 0x{:08x}", exception.get_tval())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn encoding(instr: u32, instr_len: u32) -> String {
+    if instr_len == 2 {
+        format!("(0x{:04x})", instr & 0xFFFF)
+    } else {
+        format!("(0x{instr:08x})")
+    }
+}
+
+// Nombre de la excepcion en spike y si muestra tval
+fn exception_name(exc_type: ExceptionType) -> (&'static str, bool) {
+    match exc_type {
+        ExceptionType::InstructionAddressMisaligned => ("trap_instruction_address_misaligned", true),
+        ExceptionType::InstructionAccessFault => ("trap_instruction_access_fault", true),
+        ExceptionType::IllegalInstruction => ("trap_illegal_instruction", true),
+        ExceptionType::Breakpoint => ("trap_breakpoint", true),
+        ExceptionType::LoadAddressMisaligned => ("trap_load_address_misaligned", true),
+        ExceptionType::LoadAccessFault => ("trap_load_access_fault", true),
+        ExceptionType::StoreAmoAddressMisaligned => ("trap_store_address_misaligned", true),
+        ExceptionType::StoreAmoAccessFault => ("trap_store_access_fault", true),
+        ExceptionType::EnviromentCallFromUMode => ("trap_user_ecall", false),
+        ExceptionType::EnviromentCallFromSMode => ("trap_supervisor_ecall", false),
+        ExceptionType::EnviromentCallFromMMode => ("trap_machine_ecall", false),
+        ExceptionType::InstructionPageFault => ("trap_instruction_page_fault", true),
+        ExceptionType::LoadPageFault => ("trap_load_page_fault", true),
+        ExceptionType::StoreAmoPageFault => ("trap_store_page_fault", true),
+        ExceptionType::DoubleTrap => ("trap_double_trap", false),
+        ExceptionType::SoftwareCheck => ("trap_software_check", true),
+        ExceptionType::HardwareError => ("trap_hardware_error", true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use crate::interpreter::Interpreter;
+
+    #[test]
+    fn matches_spike_commit_log() {
+        let program: [u32; 7] = [
+            0x000062b7, // lui t0, 0x6
+            0x3002a073, // csrs mstatus, t0
+            0xf0028553, // fmv.w.x fa0, t0
+            0x80001337, // lui t1, 0x80001
+            0x00532823, // sw t0, 16(t1)
+            0x34029073, // csrw mscratch, t0
+            0x00000000, // unimp
+        ];
+
+        let dir = env::temp_dir();
+        let bin = dir.join(format!("riscv_emu_trace_{}.bin", process::id()));
+        let log = dir.join(format!("riscv_emu_trace_{}.log", process::id()));
+        fs::write(&bin, program.iter().flat_map(|instr| instr.to_le_bytes()).collect::<Vec<u8>>()).unwrap();
+
+        let mut interpreter = Interpreter::with_harts(1);
+        interpreter.load_bin(bin.to_str().unwrap()).unwrap();
+        interpreter.set_pc(0x80000000);
+        interpreter.enable_trace(log.to_str().unwrap()).unwrap();
+        interpreter.run_for(program.len() as u64);
+
+        // Al soltar el tracer se vacia el buffer
+        interpreter.tracer = None;
+        let trace = fs::read_to_string(&log).unwrap();
+        fs::remove_file(&bin).unwrap();
+        fs::remove_file(&log).unwrap();
+
+        let expected = "\
+core   0: 0x80000000 (0x000062b7) lui     t0, 0x6
+core   0: 3 0x80000000 (0x000062b7) x5  0x00006000
+core   0: 0x80000004 (0x3002a073) csrs    mstatus, t0
+core   0: 3 0x80000004 (0x3002a073) c768_mstatus 0x80006000
+core   0: 0x80000008 (0xf0028553) fmv.w.x fa0, t0
+core   0: 3 0x80000008 (0xf0028553) f10 0xffffffff00006000
+core   0: 0x8000000c (0x80001337) lui     t1, 0x80001
+core   0: 3 0x8000000c (0x80001337) x6  0x80001000
+core   0: 0x80000010 (0x00532823) sw      t0, 16(t1)
+core   0: 3 0x80000010 (0x00532823) mem 0x80001010 0x00006000
+core   0: 0x80000014 (0x34029073) csrw    mscratch, t0
+core   0: 3 0x80000014 (0x34029073) c832_mscratch 0x00006000
+core   0: 0x80000018 (0x0000) unimp
+core   0: exception trap_illegal_instruction, epc 0x80000018
+core   0:           tval 0x00000000
+";
+
+        assert_eq!(trace, expected);
+    }
+}
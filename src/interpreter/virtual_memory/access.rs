@@ -0,0 +1,153 @@
+// Accesos a datos de las instrucciones de carga y almacenamiento a partir de
+// una direccion virtual.
+//
+// Los accesos desalineados provocan una excepcion o se emulan byte a byte segun
+// la politica del bus. Si cruzan de pagina cada parte se traduce por separado y
+// se comprueban las dos antes de acceder a memoria. Un almacenamiento tampoco
+// escribe nada si alguna parte cae fuera de la memoria.
+
+use crate::interpreter::{
+    bus::Bus,
+    csr::HpmEvent,
+    riscv_core::{Exception, ExceptionType, RVCore, WithVal},
+    virtual_memory::sv32::{AccessType, PAGESIZE, PhysicalAddress, translate_address_sized},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MisalignedAccess {
+    // Excepcion de direccion desalineada con la direccion virtual en mtval
+    Trap,
+    // El acceso se hace como si estuviera alineado
+    #[default]
+    Emulate,
+}
+
+// Parte de un acceso que esta dentro de una sola pagina
+struct AccessPart {
+    virt_address: u32,
+    phys_address: PhysicalAddress,
+    size: u32,
+}
+
+pub fn load(core: &mut RVCore, bus: &mut Bus, address: u32, size: u32) -> Result<u64, Exception> {
+    if address.is_multiple_of(size) {
+        let phys_address = translate_address_sized(core, bus, address, size as u64, AccessType::Load)?;
+
+        let val = bus.read(&phys_address, size).with_val(address)?;
+        core.count_event(HpmEvent::Load);
+
+        return Ok(val);
+    }
+
+    if bus.misaligned_access == MisalignedAccess::Trap {
+        return Err(Exception::new(ExceptionType::LoadAddressMisaligned, address));
+    }
+
+    let parts = translate_parts(core, bus, address, size, AccessType::Load)?;
+    let mut bytes = [0u8; 8];
+    let mut index = 0;
+
+    for part in parts.iter().flatten() {
+        for offset in 0..part.size {
+            bytes[index] = bus.read_byte(&part.phys_address.wrapping_add(offset as u64)).with_val(part.virt_address)?;
+            index += 1;
+        }
+    }
+
+    core.count_event(HpmEvent::Load);
+
+    Ok(u64::from_le_bytes(bytes))
+}
+
+pub fn store(core: &mut RVCore, bus: &mut Bus, address: u32, size: u32, val: u64) -> Result<(), Exception> {
+    if address.is_multiple_of(size) {
+        let phys_address = translate_address_sized(core, bus, address, size as u64, AccessType::StoreAmo)?;
+
+        bus.write(&phys_address, size, val).with_val(address)?;
+        core.count_event(HpmEvent::Store);
+
+        return Ok(());
+    }
+
+    if bus.misaligned_access == MisalignedAccess::Trap {
+        return Err(Exception::new(ExceptionType::StoreAmoAddressMisaligned, address));
+    }
+
+    let parts = translate_parts(core, bus, address, size, AccessType::StoreAmo)?;
+
+    if let Some(part) = parts.iter().flatten().find(|part| !bus.is_mapped(part.phys_address.0, part.size)) {
+        return Err(Exception::new(ExceptionType::StoreAmoAccessFault, part.virt_address));
+    }
+
+    let bytes = val.to_le_bytes();
+    let mut index = 0;
+
+    for part in parts.iter().flatten() {
+        for offset in 0..part.size {
+            bus.write_byte(&part.phys_address.wrapping_add(offset as u64), bytes[index]).with_val(part.virt_address)?;
+            index += 1;
+        }
+    }
+
+    core.count_event(HpmEvent::Store);
+
+    Ok(())
+}
+
+// Traduce las partes del acceso a cada lado del limite de pagina. La segunda
+// solo existe si el acceso cruza de pagina
+fn translate_parts(
+    core: &mut RVCore,
+    bus: &mut Bus,
+    address: u32,
+    size: u32,
+    access_type: AccessType,
+) -> Result<[Option<AccessPart>; 2], Exception> {
+    let first_size = size.min(PAGESIZE - address % PAGESIZE);
+    let first = AccessPart {
+        virt_address: address,
+        phys_address: translate_address_sized(core, bus, address, first_size as u64, access_type)?,
+        size: first_size,
+    };
+
+    if first_size == size {
+        return Ok([Some(first), None]);
+    }
+
+    let second_address = address.wrapping_add(first_size);
+    let second = AccessPart {
+        virt_address: second_address,
+        phys_address: translate_address_sized(core, bus, second_address, (size - first_size) as u64, access_type)?,
+        size: size - first_size,
+    };
+
+    Ok([Some(first), Some(second)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::store;
+    use crate::interpreter::{
+        Interpreter,
+        riscv_core::{Exception, ExceptionType},
+        virtual_memory::sv32::PhysicalAddress,
+    };
+
+    #[test]
+    fn store_past_memory_end_writes_nothing() {
+        let mut interpreter = Interpreter::with_harts(1);
+        let dram_end = interpreter.config.dram_base + interpreter.config.dram_size as u32;
+        let address = dram_end - 2;
+
+        for byte in 0..2 {
+            interpreter.bus.write_byte(&PhysicalAddress((address + byte) as u64), 0xAA).unwrap();
+        }
+
+        let result = store(&mut interpreter.cores[0], &mut interpreter.bus, address, 4, 0x12345678);
+        assert_eq!(result, Err(Exception::new(ExceptionType::StoreAmoAccessFault, dram_end)));
+
+        for byte in 0..2 {
+            assert_eq!(interpreter.bus.read_byte(&PhysicalAddress((address + byte) as u64)), Ok(0xAA));
+        }
+    }
+}
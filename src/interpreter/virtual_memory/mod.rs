@@ -1 +1,4 @@
-pub mod sv32;
\ No newline at end of file
+pub mod access;
+pub mod pmp;
+pub mod sv32;
+pub mod tlb;
\ No newline at end of file
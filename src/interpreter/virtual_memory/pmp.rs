@@ -0,0 +1,120 @@
+// Proteccion de memoria fisica (PMP) con 16 entradas y granularidad de 4 bytes.
+//
+// Cada entrada tiene un byte de configuracion en pmpcfg0-3 y una direccion en
+// pmpaddr0-15 (bits 33-2 de la direccion fisica). La entrada de menor numero
+// que contiene algun byte del acceso decide si se permite. Si ninguna lo
+// contiene solo se permite en modo maquina.
+
+use bitfield::bitfield;
+
+use crate::interpreter::{csr::ControlAndStatus, riscv_core::PrivilegeLevel, virtual_memory::sv32::AccessType};
+
+pub const PMP_ENTRIES: usize = 16;
+
+// Modos de la direccion (campo A). Con 0 (OFF) la entrada esta desactivada
+const PMP_TOR: u8 = 1;
+const PMP_NA4: u8 = 2;
+const PMP_NAPOT: u8 = 3;
+
+bitfield! {
+    #[derive(Clone, Copy)]
+    pub struct PmpConfig(u8);
+    pub get_r, set_r: 0;
+    pub get_w, set_w: 1;
+    pub get_x, set_x: 2;
+    pub get_a, set_a: 4, 3;
+    _, _: 6, 5; // WPRI
+    pub get_l, set_l: 7;
+}
+
+impl PmpConfig {
+    // Los bits reservados son 0 y la combinacion R=0 W=1 esta reservada, se
+    // queda sin W
+    pub fn legalize(val: u8) -> Self {
+        let mut cfg = PmpConfig(val & 0x9F);
+
+        if !cfg.get_r() && cfg.get_w() {
+            cfg.set_w(false);
+        }
+
+        cfg
+    }
+
+    fn allows(&self, access_type: AccessType) -> bool {
+        match access_type {
+            AccessType::Load => self.get_r(),
+            AccessType::StoreAmo => self.get_w(),
+            AccessType::Execute => self.get_x(),
+        }
+    }
+}
+
+// Rango [inicio, fin) de direcciones fisicas que cubre la entrada
+fn get_range(csrs: &ControlAndStatus, entry: usize) -> Option<(u64, u64)> {
+    let pmpaddr = csrs.read_pmpaddr(entry) as u64;
+
+    match csrs.read_pmpcfg(entry).get_a() {
+        PMP_TOR => {
+            let start = if entry == 0 { 0 } else { (csrs.read_pmpaddr(entry - 1) as u64) << 2 };
+
+            Some((start, pmpaddr << 2))
+        }
+        PMP_NA4 => Some((pmpaddr << 2, (pmpaddr << 2) + 4)),
+        PMP_NAPOT => {
+            // Los unos de menor peso indican el tamaño: 2^(unos + 3) bytes
+            let ones = pmpaddr.trailing_ones();
+            let start = (pmpaddr & !((1u64 << ones) - 1)) << 2;
+
+            Some((start, start + (1u64 << (ones + 3))))
+        }
+        _ => None,
+    }
+}
+
+// Comprueba el acceso de size bytes a partir de phys_address
+pub fn check_pmp(
+    csrs: &ControlAndStatus,
+    phys_address: u64,
+    size: u64,
+    access_type: AccessType,
+    priv_level: PrivilegeLevel,
+) -> bool {
+    let end = phys_address + size;
+
+    for entry in 0..PMP_ENTRIES {
+        let Some((start, region_end)) = get_range(csrs, entry) else {
+            continue;
+        };
+
+        if phys_address >= region_end || end <= start {
+            continue;
+        }
+
+        // El acceso tiene que estar entero dentro de la region
+        if phys_address < start || end > region_end {
+            return false;
+        }
+
+        let cfg = csrs.read_pmpcfg(entry);
+
+        // En modo maquina solo se aplican las entradas bloqueadas
+        if priv_level == PrivilegeLevel::Machine && !cfg.get_l() {
+            return true;
+        }
+
+        return cfg.allows(access_type);
+    }
+
+    priv_level == PrivilegeLevel::Machine
+}
+
+// Una entrada bloqueada no se puede modificar. Tampoco su pmpaddr ni el de la
+// anterior si es el inicio de un rango TOR
+pub fn is_pmpaddr_locked(csrs: &ControlAndStatus, entry: usize) -> bool {
+    let next_is_locked_tor = entry + 1 < PMP_ENTRIES && {
+        let next = csrs.read_pmpcfg(entry + 1);
+        next.get_l() && next.get_a() == PMP_TOR
+    };
+
+    csrs.read_pmpcfg(entry).get_l() || next_is_locked_tor
+}
@@ -1,14 +1,15 @@
 use crate::interpreter::{
-    bus::Bus, csr::MStatus, riscv_core::{Exception, ExceptionType, PrivilegeLevel, RVCore}
+    bus::Bus, csr::HpmEvent, riscv_core::{Exception, ExceptionType, PrivilegeLevel, RVCore}, trace::MemAccess,
+    virtual_memory::pmp::check_pmp
 };
 
 use bitfield::bitfield;
 
-const PAGESIZE: u32 = 2u32.pow(12);
+pub const PAGESIZE: u32 = 2u32.pow(12);
 const LEVELS: u32 = 2;
 const PTESIZE: u32 = 4;
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum AccessType {
     Load,
     StoreAmo,
@@ -108,24 +109,60 @@ fn check_access(core: &mut RVCore, pte: &PageTableEntry, access_type: &AccessTyp
     }
 }
 
+// Con MPRV los accesos a datos en modo maquina usan el privilegio de MPP
+fn effective_privilege(core: &RVCore, access_type: AccessType) -> PrivilegeLevel {
+    let mstatus = core.control_and_status.read_mstatus_unchecked();
+
+    if core.privilege_level == PrivilegeLevel::Machine
+        && mstatus.get_mprv()
+        && access_type != AccessType::Execute
+    {
+        PrivilegeLevel::new(mstatus.get_mpp())
+    } else {
+        core.privilege_level
+    }
+}
+
+// Las regiones de PMP estan alineadas a 4 bytes, asi que basta con comprobar
+// el primer byte de los accesos alineados de hasta 4 bytes
 pub fn translate_address(
     core: &mut RVCore,
     bus: &mut Bus,
     virt_address: u32,
     access_type: AccessType
 ) -> Result<PhysicalAddress, Exception> {
-    let mstatus = core.control_and_status.read_mstatus_unchecked();
-    
-    if core.privilege_level == PrivilegeLevel::Machine {
-        if mstatus.get_mprv() && (access_type == AccessType::Load || access_type == AccessType::StoreAmo) {
-            let mpp = mstatus.get_mpp();
-            translate(core, bus, virt_address, access_type, PrivilegeLevel::new(mpp))
-        } else  {
-            Ok(PhysicalAddress(virt_address as u64))
-        }
-    } else {
-        translate(core, bus, virt_address, access_type, core.privilege_level)
+    translate_address_sized(core, bus, virt_address, 1, access_type)
+}
+
+// Traduce un acceso de size bytes que no cruza de pagina y comprueba con PMP
+// la direccion fisica resultante
+pub fn translate_address_sized(
+    core: &mut RVCore,
+    bus: &mut Bus,
+    virt_address: u32,
+    size: u64,
+    access_type: AccessType
+) -> Result<PhysicalAddress, Exception> {
+    let effective_priv = effective_privilege(core, access_type);
+
+    let phys_address = translate(core, bus, virt_address, access_type, effective_priv)?;
+
+    if !check_pmp(&core.control_and_status, phys_address.0, size, access_type, effective_priv) {
+        return Err(Exception::new(access_type.get_access_fault_exception(), virt_address));
     }
+
+    // Para la traza solo interesan los accesos a datos
+    if let Some(log) = &mut core.commit_log
+        && access_type != AccessType::Execute
+    {
+        log.mem.push(MemAccess {
+            address: virt_address,
+            phys_address: phys_address.0,
+            access_type,
+        });
+    }
+
+    Ok(phys_address)
 }
 
 fn translate(
@@ -146,66 +183,187 @@ fn translate(
     }
 
     let va = VirtAddress(virt_address);
+    let vpn = virt_address >> 12;
+    let asid = satp.get_asid();
+
+    // Si hay que marcar A o D en la PTE se recorre la tabla de paginas
+    if let Some(entry) = core.tlb.lookup(vpn, asid) {
+        let pte = entry.get_pte();
+        let needs_update = !pte.get_a() || (access_type == AccessType::StoreAmo && !pte.get_d());
+
+        if !needs_update {
+            if !check_access(core, &pte, &access_type, effective_priv) {
+                return Err(Exception::new(access_type.get_page_fault_exception(), virt_address));
+            }
+
+            let mut phys_address = PhysicalAddress(0);
+            phys_address.set_page_offset(va.get_page_offset() as u64);
+            phys_address.set_ppn(entry.get_ppn() as u64);
+
+            return Ok(phys_address);
+        }
+    } else {
+        core.count_event(HpmEvent::TlbMiss);
+    }
+
+    walk_page_table(core, bus, virt_address, access_type, effective_priv)
+}
+
+// Recorre la tabla de paginas siguiendo el algoritmo de la especificacion
+// privilegiada (seccion "Virtual Address Translation Process") y guarda la
+// traduccion en la TLB. Los numeros de los pasos son los de la especificacion.
+//
+// Si A = 0, o D = 0 en una escritura, con menvcfg.ADUE = 0 se lanza un page
+// fault para que el software actualice la PTE (Svade). Con ADUE = 1 se
+// actualiza aqui (Svadu); como los harts no se ejecutan a la vez la lectura y
+// la escritura de la PTE son atomicas.
+fn walk_page_table(
+    core: &mut RVCore,
+    bus: &mut Bus,
+    virt_address: u32,
+    access_type: AccessType,
+    effective_priv: PrivilegeLevel
+) -> Result<PhysicalAddress, Exception> {
+    let page_fault = Exception::new(access_type.get_page_fault_exception(), virt_address);
+    let access_fault = Exception::new(access_type.get_access_fault_exception(), virt_address);
+
+    let satp = core.control_and_status.read_satp_unchecked();
+    let va = VirtAddress(virt_address);
+    let vpn = [va.get_vpn0(), va.get_vpn1()];
 
-    let mut a = satp.get_ppn() * PAGESIZE;
-    let mut i = LEVELS as i32 - 1;
+    // 1
+    let mut a = satp.get_ppn() as u64 * PAGESIZE as u64;
+    let mut i = LEVELS as usize - 1;
+    // El bit G de una PTE intermedia se aplica a todas las paginas que cuelgan de ella
+    let mut global = false;
 
-    while i >= 0 {
-        let pte_addr = if i == 1 {
-            a + va.get_vpn1() * PTESIZE
-        } else {
-            a + va.get_vpn0() * PTESIZE
-        };
+    let (mut pte, pte_address) = loop {
+        // 2. Los accesos a la tabla de paginas se comprueban con PMP como
+        // accesos en modo supervisor
+        let pte_address = PhysicalAddress(a + (vpn[i] * PTESIZE) as u64);
 
-        let pte = PageTableEntry(bus.read_word(&PhysicalAddress(pte_addr as u64))?);
+        if !check_pmp(&core.control_and_status, pte_address.0, PTESIZE as u64, AccessType::Load, PrivilegeLevel::Supervisor) {
+            return Err(access_fault);
+        }
 
+        let pte = PageTableEntry(bus.read_aligned_word(&pte_address).map_err(|_| access_fault)?);
+
+        // 3
         if !pte.get_v() || (!pte.get_r() && pte.get_w()) {
-            return Err(Exception::new(access_type.get_page_fault_exception(), virt_address));
+            return Err(page_fault);
         }
 
+        global |= pte.get_g();
+
+        // 4
         if pte.get_r() || pte.get_x() {
-            if i > 0 && (pte.get_ppn0() != 0) {
-                return Err(Exception::new(access_type.get_page_fault_exception(), virt_address));
-            }
+            break (pte, pte_address);
+        }
 
-            // TODO PASO 7
-            // Determine if the requested memory access is allowed by the pte.r, pte.w, and pte.x bits, given the
-            // Shadow Stack Memory Protection rules. If not, stop and raise an access-fault exception
+        // En las PTEs intermedias D, A y U estan reservados
+        if pte.get_d() || pte.get_a() || pte.get_u() || i == 0 {
+            return Err(page_fault);
+        }
 
-            if !check_access(core, &pte, &access_type, effective_priv) {
-                return Err(Exception::new(access_type.get_page_fault_exception(), virt_address));
-            }
+        i -= 1;
+        a = pte.get_ppn() as u64 * PAGESIZE as u64;
+    };
 
-            if pte.get_a() || (access_type == AccessType::StoreAmo && pte.get_d()) {
-                let mut new_pte = PageTableEntry(bus.read_word(&PhysicalAddress(pte_addr as u64))?);
+    // 5
+    if !check_access(core, &pte, &access_type, effective_priv) {
+        return Err(page_fault);
+    }
 
-                if new_pte.0 == pte.0 {
-                    new_pte.set_a(true);
-                    if access_type == AccessType::StoreAmo {
-                        new_pte.set_d(true);
-                    }
+    // 6. Megapagina desalineada
+    if i > 0 && pte.get_ppn0() != 0 {
+        return Err(page_fault);
+    }
 
-                    bus.write_aligned_word(&PhysicalAddress(pte_addr as u64), new_pte.0).unwrap();
-                } else {
-                    continue;
-                }
-            }
+    // 7
+    if !pte.get_a() || (access_type == AccessType::StoreAmo && !pte.get_d()) {
+        if !core.control_and_status.read_menvcfg_adue() {
+            return Err(page_fault);
+        }
 
-            let mut phys_addres = PhysicalAddress(0);
-            phys_addres.set_page_offset(va.get_page_offset() as u64);
-            if i > 0 {
-                phys_addres.set_ppn0(va.get_vpn0() as u64);
-                phys_addres.set_ppn1(pte.get_ppn1() as u64);
-            } else {
-                phys_addres.set_ppn(pte.get_ppn() as u64);
-            }
+        if !check_pmp(&core.control_and_status, pte_address.0, PTESIZE as u64, AccessType::StoreAmo, PrivilegeLevel::Supervisor) {
+            return Err(access_fault);
+        }
+
+        pte.set_a(true);
+
+        if access_type == AccessType::StoreAmo {
+            pte.set_d(true);
+        }
+
+        bus.write_aligned_word(&pte_address, pte.0).map_err(|_| access_fault)?;
+    }
+
+    core.tlb.insert(virt_address >> 12, satp.get_asid(), global, i > 0, PageTableEntry(pte.0));
+
+    // 8
+    let mut phys_address = PhysicalAddress(0);
+    phys_address.set_page_offset(va.get_page_offset() as u64);
+
+    if i > 0 {
+        phys_address.set_ppn0(va.get_vpn0() as u64);
+        phys_address.set_ppn1(pte.get_ppn1() as u64);
+    } else {
+        phys_address.set_ppn(pte.get_ppn() as u64);
+    }
+
+    Ok(phys_address)
+}
+
+// Traduccion para el depurador. Recorre la tabla de paginas como un acceso a
+// datos del hart pero sin comprobar permisos ni PMP, sin marcar A/D, sin tocar
+// la TLB y sin contar eventos ni trazar, asi que no cambia el estado de la
+// maquina. Devuelve None si la direccion no esta mapeada
+pub fn debug_translate(core: &RVCore, bus: &mut Bus, virt_address: u32) -> Option<PhysicalAddress> {
+    let satp = core.control_and_status.read_satp_unchecked();
+
+    if effective_privilege(core, AccessType::Load) == PrivilegeLevel::Machine || !satp.get_mode() {
+        return Some(PhysicalAddress(virt_address as u64));
+    }
+
+    let va = VirtAddress(virt_address);
+    let vpn = [va.get_vpn0(), va.get_vpn1()];
+
+    let mut a = satp.get_ppn() as u64 * PAGESIZE as u64;
+    let mut i = LEVELS as usize - 1;
+
+    let pte = loop {
+        let pte_address = PhysicalAddress(a + (vpn[i] * PTESIZE) as u64);
+        let pte = PageTableEntry(bus.read_aligned_word(&pte_address).ok()?);
 
-            return Ok(phys_addres);
+        if !pte.get_v() || (!pte.get_r() && pte.get_w()) {
+            return None;
+        }
+
+        if pte.get_r() || pte.get_x() {
+            break pte;
+        }
+
+        if i == 0 {
+            return None;
         }
 
         i -= 1;
-        a = pte.get_ppn() * PAGESIZE;
+        a = pte.get_ppn() as u64 * PAGESIZE as u64;
+    };
+
+    let mut phys_address = PhysicalAddress(0);
+    phys_address.set_page_offset(va.get_page_offset() as u64);
+
+    if i > 0 {
+        if pte.get_ppn0() != 0 {
+            return None;
+        }
+
+        phys_address.set_ppn0(va.get_vpn0() as u64);
+        phys_address.set_ppn1(pte.get_ppn1() as u64);
+    } else {
+        phys_address.set_ppn(pte.get_ppn() as u64);
     }
 
-    Err(Exception::new(access_type.get_access_fault_exception(), virt_address))
+    Some(phys_address)
 }
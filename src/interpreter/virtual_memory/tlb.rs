@@ -0,0 +1,108 @@
+// TLB de correspondencia directa para Sv32. Cada entrada guarda la traduccion
+// de una pagina de 4 KiB (las megapaginas ocupan una entrada por cada pagina
+// que se usa) junto con la PTE hoja, para comprobar los permisos en cada acceso.
+//
+// Las entradas globales valen para cualquier ASID. Se vacia con sfence.vma y
+// al escribir en satp, en este caso conservando las entradas globales.
+
+use crate::interpreter::virtual_memory::sv32::PageTableEntry;
+
+const TLB_ENTRIES: usize = 256;
+
+#[derive(Clone, Copy)]
+pub struct TlbEntry {
+    vpn: u32,
+    asid: u32,
+    global: bool,
+    megapage: bool,
+    pte: u32,
+}
+
+impl TlbEntry {
+    pub fn get_pte(&self) -> PageTableEntry {
+        PageTableEntry(self.pte)
+    }
+
+    // Numero de pagina fisica de la pagina de 4 KiB
+    pub fn get_ppn(&self) -> u32 {
+        let pte = self.get_pte();
+
+        if self.megapage {
+            (pte.get_ppn1() << 10) | (self.vpn & 0x3FF)
+        } else {
+            pte.get_ppn()
+        }
+    }
+
+    fn matches_asid(&self, asid: u32) -> bool {
+        self.global || self.asid == asid
+    }
+
+    // Una megapagina contiene todas las paginas con el mismo vpn1
+    fn matches_address(&self, vpn: u32) -> bool {
+        if self.megapage {
+            self.vpn >> 10 == vpn >> 10
+        } else {
+            self.vpn == vpn
+        }
+    }
+}
+
+pub struct Tlb {
+    entries: Vec<Option<TlbEntry>>,
+}
+
+impl Default for Tlb {
+    fn default() -> Self {
+        Self {
+            entries: vec![None; TLB_ENTRIES],
+        }
+    }
+}
+
+impl Tlb {
+    pub fn lookup(&self, vpn: u32, asid: u32) -> Option<TlbEntry> {
+        self.entries[vpn as usize % TLB_ENTRIES].filter(|entry| entry.vpn == vpn && entry.matches_asid(asid))
+    }
+
+    pub fn insert(&mut self, vpn: u32, asid: u32, global: bool, megapage: bool, pte: PageTableEntry) {
+        self.entries[vpn as usize % TLB_ENTRIES] = Some(TlbEntry {
+            vpn,
+            asid,
+            global,
+            megapage,
+            pte: pte.0,
+        });
+    }
+
+    pub fn flush_all(&mut self) {
+        self.entries.fill(None);
+    }
+
+    // Escritura en satp. Las entradas globales valen para todos los espacios de
+    // direcciones, asi que se mantienen
+    pub fn flush_non_global(&mut self) {
+        for slot in &mut self.entries {
+            if slot.is_some_and(|entry| !entry.global) {
+                *slot = None;
+            }
+        }
+    }
+
+    // sfence.vma con rs1 y/o rs2 distintos de x0. Las entradas globales solo se
+    // eliminan si no se indica ASID
+    pub fn flush(&mut self, vpn: Option<u32>, asid: Option<u32>) {
+        for slot in &mut self.entries {
+            let Some(entry) = slot else {
+                continue;
+            };
+
+            let address_matches = vpn.is_none_or(|vpn| entry.matches_address(vpn));
+            let asid_matches = asid.is_none_or(|asid| !entry.global && entry.asid == asid);
+
+            if address_matches && asid_matches {
+                *slot = None;
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
 pub mod interpreter;
 mod peripherals;
+pub mod utils;
 
 #[cfg(test)]
 mod tests;
@@ -1,12 +1,307 @@
-use riscv_emu::interpreter::Interpreter;
+use std::{env, fs::File, io::Read, process};
+
+use riscv_emu::{
+    interpreter::{ExitStatus, Interpreter, LoadError, MachineConfig, gdb},
+    utils::parse_number,
+};
+
+// Direcciones que usa fw_jump de OpenSBI en RV32
+const DEFAULT_KERNEL_ADDRESS: u32 = 0x80400000;
+const DEFAULT_DTB_ADDRESS: u32 = 0x82200000;
+
+// En los riscv-tests fromhost esta 0x40 bytes detras de tohost
+const DEFAULT_FROMHOST_OFFSET: u32 = 0x40;
+
+const ELF_MAGIC: [u8; 4] = [0x7F, b'E', b'L', b'F'];
+
+const USAGE: &str = "\
+Uso: riscv_emu [opciones] [imagen[@direccion]...]
+
+Las imagenes se cargan en el orden indicado. Los ELF se reconocen por su
+contenido y los .hex por la extension, el resto se cargan como binarios. Si no
+se indica direccion los .hex y los binarios se cargan al principio de la DRAM.
+Todos los harts empiezan en --entry o, si no se indica, en el punto de entrada
+de la primera imagen si es un ELF o en su direccion de carga si no lo es.
+
+Opciones:
+  --hex FICHERO[@DIR]      carga un fichero .hex de Intel
+  --bin FICHERO[@DIR]      carga un binario
+  --elf FICHERO            carga un ELF en las direcciones de sus segmentos
+  --kernel FICHERO[@DIR]   carga el kernel (0x80400000 por defecto)
+  --initrd FICHERO@DIR     carga el initrd
+  --dtb FICHERO[@DIR]      carga el device tree (0x82200000 por defecto) y deja
+                           el mhartid en a0 y su direccion en a1
+  --entry DIR              pc inicial de todos los harts
+  --dram-base DIR          direccion de la DRAM (0x80000000)
+  --dram-size TAMAÑO       tamaño de la DRAM con sufijo K, M o G opcional (8M)
+  --harts N                numero de harts (1)
+  --max-instrs N           se detiene despues de ejecutar N instrucciones (no
+                           se puede usar con --gdb)
+  --tohost DIR             direccion de tohost del HTIF
+  --fromhost DIR           direccion de fromhost (tohost + 0x40 por defecto)
+  --trace FICHERO          guarda la traza de ejecucion en formato de Spike
+  --gdb PUERTO             espera a que GDB se conecte a localhost:PUERTO
+  -h, --help               muestra esta ayuda";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Hex,
+    Bin,
+    Elf,
+}
+
+struct Image {
+    path: String,
+    format: Format,
+    address: Option<u32>,
+}
+
+#[derive(Default)]
+struct Options {
+    config: MachineConfig,
+    images: Vec<Image>,
+    kernel: Option<Image>,
+    initrd: Option<Image>,
+    dtb: Option<Image>,
+    entry: Option<u32>,
+    max_instrs: Option<u64>,
+    to_host: Option<u32>,
+    from_host: Option<u32>,
+    trace: Option<String>,
+    gdb_port: Option<u16>,
+}
 
 fn main() {
-    let mut interpreter = Interpreter::default();
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return;
+        }
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            process::exit(2);
+        }
+    };
+
+    if let Err(err) = options.config.validate() {
+        eprintln!("Configuracion de la maquina no valida: {err}");
+        process::exit(2);
+    }
+
+    let mut interpreter = Interpreter::with_config(options.config.clone());
+
+    let mut entry = options.entry;
+
+    let boot_images = [&options.kernel, &options.initrd, &options.dtb];
+    for image in options.images.iter().chain(boot_images.into_iter().flatten()) {
+        let address = load_image(&mut interpreter, image).unwrap_or_else(|err| {
+            eprintln!("No se pudo cargar {}: {err}", image.path);
+            process::exit(1);
+        });
+
+        // La primera imagen es la que se ejecuta
+        entry.get_or_insert(address);
+    }
+
+    let Some(entry) = entry else {
+        eprintln!("No se ha indicado ninguna imagen\n\n{USAGE}");
+        process::exit(2);
+    };
+
+    interpreter.set_pc(entry);
+
+    if let Some(dtb_address) = options.dtb.as_ref().and_then(|dtb| dtb.address) {
+        interpreter.set_boot_args(dtb_address);
+    }
+
+    // Si no se indica tohost se mantiene el que haya definido un ELF
+    if let (Some(to_host), Some(from_host)) = (options.to_host, options.from_host) {
+        interpreter.set_htif(to_host as usize, from_host as usize);
+    }
+
+    if let Some(path) = &options.trace
+        && let Err(err) = interpreter.enable_trace(path)
+    {
+        eprintln!("No se pudo crear la traza {path}: {err}");
+        process::exit(1);
+    }
+
+    let status = match options.gdb_port {
+        Some(port) => match gdb::serve(&mut interpreter, ("127.0.0.1", port)) {
+            Ok(Some(status)) => status,
+            Ok(None) => return,
+            Err(err) => {
+                eprintln!("Error en la conexion con GDB: {err}");
+                process::exit(1);
+            }
+        },
+        None => match options.max_instrs {
+            Some(max_instrs) => interpreter.run_for(max_instrs),
+            None => interpreter.run(),
+        },
+    };
+
+    match status {
+        ExitStatus::Halted { exit_code } => process::exit(exit_code as i32),
+        ExitStatus::FatalError { pc, exception } => {
+            eprintln!("La ejecucion se ha detenido en 0x{pc:08x}: {exception:?}");
+
+            if let Ok((instr, text)) = interpreter.disassemble_at(pc) {
+                eprintln!("    0x{pc:08x}: {instr:08x}  {text}");
+            }
+
+            process::exit(1);
+        }
+        status => {
+            eprintln!("La ejecucion se ha detenido: {status:?}");
+            process::exit(1);
+        }
+    }
+}
+
+// Carga la imagen y devuelve la direccion en la que empieza a ejecutarse
+fn load_image(interpreter: &mut Interpreter, image: &Image) -> Result<u32, LoadError> {
+    let address = image.address.unwrap_or(interpreter.get_config().dram_base);
+
+    match image.format {
+        Format::Hex => interpreter.load_hex_at(&image.path, address)?,
+        Format::Bin => interpreter.load_bin_at(&image.path, address)?,
+        Format::Elf => {
+            interpreter.load_elf(&image.path)?;
+            return Ok(interpreter.get_pc());
+        }
+    }
+
+    Ok(address)
+}
+
+// Devuelve None si se ha pedido la ayuda
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') {
+            options.images.push(parse_image(&arg, None)?);
+            continue;
+        }
+
+        if arg == "-h" || arg == "--help" {
+            return Ok(None);
+        }
+
+        let value = args.next().ok_or_else(|| format!("Falta el valor de {arg}"))?;
+
+        match arg.as_str() {
+            "--hex" => options.images.push(parse_image(&value, Some(Format::Hex))?),
+            "--bin" => options.images.push(parse_image(&value, Some(Format::Bin))?),
+            "--elf" => options.images.push(parse_image(&value, Some(Format::Elf))?),
+            "--kernel" => {
+                let mut kernel = parse_image(&value, None)?;
+
+                if kernel.format != Format::Elf {
+                    kernel.address.get_or_insert(DEFAULT_KERNEL_ADDRESS);
+                }
+
+                options.kernel = Some(kernel);
+            }
+            "--initrd" => {
+                let initrd = parse_image(&value, Some(Format::Bin))?;
+
+                if initrd.address.is_none() {
+                    return Err(format!("Falta la direccion del initrd: {value}"));
+                }
+
+                options.initrd = Some(initrd);
+            }
+            "--dtb" => {
+                let mut dtb = parse_image(&value, Some(Format::Bin))?;
+                dtb.address.get_or_insert(DEFAULT_DTB_ADDRESS);
+
+                options.dtb = Some(dtb);
+            }
+            "--entry" => options.entry = Some(parse_value(&arg, &value, parse_number)?),
+            "--dram-base" => options.config.dram_base = parse_value(&arg, &value, parse_number)?,
+            "--dram-size" => options.config.dram_size = parse_value(&arg, &value, parse_size)?,
+            "--harts" => options.config.num_harts = parse_value(&arg, &value, |text| text.parse().ok())?,
+            "--max-instrs" => options.max_instrs = Some(parse_value(&arg, &value, |text| text.parse().ok())?),
+            "--tohost" => options.to_host = Some(parse_value(&arg, &value, parse_number)?),
+            "--fromhost" => options.from_host = Some(parse_value(&arg, &value, parse_number)?),
+            "--trace" => options.trace = Some(value),
+            "--gdb" => options.gdb_port = Some(parse_value(&arg, &value, |text| text.parse().ok())?),
+            _ => return Err(format!("Opcion desconocida: {arg}")),
+        }
+    }
+
+    // Con GDB la ejecucion la controla el depurador
+    if options.gdb_port.is_some() && options.max_instrs.is_some() {
+        return Err("--max-instrs no se puede usar con --gdb".to_string());
+    }
+
+    if let Some(to_host) = options.to_host
+        && options.from_host.is_none()
+    {
+        let from_host = to_host
+            .checked_add(DEFAULT_FROMHOST_OFFSET)
+            .ok_or_else(|| format!("No cabe fromhost detras de tohost 0x{to_host:08x}, indica --fromhost"))?;
+
+        options.from_host = Some(from_host);
+    }
+
+    Ok(Some(options))
+}
+
+fn parse_value<T>(arg: &str, value: &str, parse: impl Fn(&str) -> Option<T>) -> Result<T, String> {
+    parse(value).ok_or_else(|| format!("Valor no valido para {arg}: {value}"))
+}
+
+// FICHERO[@DIRECCION]. Sin formato se deduce del fichero
+fn parse_image(text: &str, format: Option<Format>) -> Result<Image, String> {
+    let (path, address) = match text.rsplit_once('@') {
+        Some((path, address)) => {
+            let address = parse_number(address).ok_or_else(|| format!("Direccion no valida: {address}"))?;
+            (path, Some(address))
+        }
+        None => (text, None),
+    };
+
+    let format = match format {
+        Some(format) => format,
+        None => detect_format(path)?,
+    };
+
+    if format == Format::Elf && address.is_some() {
+        return Err(format!("Los ELF se cargan en las direcciones de sus segmentos: {text}"));
+    }
+
+    Ok(Image {
+        path: path.to_string(),
+        format,
+        address,
+    })
+}
+
+fn detect_format(path: &str) -> Result<Format, String> {
+    let mut magic = [0u8; 4];
+    let mut file = File::open(path).map_err(|err| format!("No se pudo abrir {path}: {err}"))?;
+
+    if file.read_exact(&mut magic).is_ok() && magic == ELF_MAGIC {
+        Ok(Format::Elf)
+    } else if path.ends_with(".hex") {
+        Ok(Format::Hex)
+    } else {
+        Ok(Format::Bin)
+    }
+}
 
-    // interpreter.load_hex("rv_tests/rv32ui-p-ld_st.hex");
-    interpreter.load_hex("rv_tests/rv32ui-v-or.hex");
-    // interpreter.load_hex("bin/xv6_32/kernel.hex");
-    // interpreter.load_bin("fw/fw_jump.bin");
+// Numero de bytes con sufijo K, M o G opcional
+fn parse_size(text: &str) -> Option<usize> {
+    let (number, shift) = match text.as_bytes().last()? {
+        b'K' | b'k' => (&text[..text.len() - 1], 10),
+        b'M' | b'm' => (&text[..text.len() - 1], 20),
+        b'G' | b'g' => (&text[..text.len() - 1], 30),
+        _ => (text, 0),
+    };
 
-    interpreter.run();
+    (parse_number(number)? as usize).checked_mul(1 << shift)
 }
@@ -0,0 +1,149 @@
+// CLINT compatible con el de SiFive (y con los dispositivos MSWI y MTIMER del ACLINT)
+//
+// 0x0000 + 4 * hart  msip
+// 0x4000 + 8 * hart  mtimecmp
+// 0xBFF8             mtime
+
+const MSIP_BASE: usize = 0x0000;
+const MTIMECMP_BASE: usize = 0x4000;
+const MTIME: usize = 0xBFF8;
+const MTIME_END: usize = MTIME + 8;
+
+use crate::{
+    interpreter::{
+        device::{BusError, Device},
+        snapshot::{SnapshotError, SnapshotReader, SnapshotWriter},
+    },
+    utils::write_byte_u64,
+};
+
+pub struct Clint {
+    msip: Vec<u32>,
+    mtimecmp: Vec<u64>,
+    mtime: u64,
+}
+
+impl Clint {
+    pub fn new(num_harts: usize) -> Self {
+        Self {
+            msip: vec![0; num_harts],
+            // mtimecmp no tiene valor de reset, se usa el maximo para que no salte el timer
+            mtimecmp: vec![u64::MAX; num_harts],
+            mtime: 0,
+        }
+    }
+
+    pub fn get_mtime(&self) -> u64 {
+        self.mtime
+    }
+
+    pub fn set_mtime(&mut self, mtime: u64) {
+        self.mtime = mtime;
+    }
+
+    pub fn get_mtimecmp(&self, hart_id: usize) -> u64 {
+        self.mtimecmp[hart_id]
+    }
+
+    pub fn is_software_interrupt_pending(&self, hart_id: usize) -> bool {
+        self.msip[hart_id] & 1 != 0
+    }
+
+    pub fn is_timer_interrupt_pending(&self, hart_id: usize) -> bool {
+        self.mtime >= self.mtimecmp[hart_id]
+    }
+}
+
+impl Device for Clint {
+    fn read_u8(&mut self, offset: u64) -> Result<u8, BusError> {
+        let address = offset as usize;
+
+        let val = match address {
+            MSIP_BASE..MTIMECMP_BASE => {
+                let hart = (address - MSIP_BASE) / 4;
+
+                match self.msip.get(hart) {
+                    Some(msip) => msip.to_le_bytes()[address % 4],
+                    None => 0,
+                }
+            }
+            MTIMECMP_BASE..MTIME => {
+                let hart = (address - MTIMECMP_BASE) / 8;
+
+                match self.mtimecmp.get(hart) {
+                    Some(mtimecmp) => mtimecmp.to_le_bytes()[address % 8],
+                    None => 0,
+                }
+            }
+            MTIME..MTIME_END => self.mtime.to_le_bytes()[address - MTIME],
+            _ => 0,
+        };
+
+        Ok(val)
+    }
+
+    fn write_u8(&mut self, offset: u64, val: u8) -> Result<(), BusError> {
+        let address = offset as usize;
+
+        match address {
+            MSIP_BASE..MTIMECMP_BASE => {
+                let hart = (address - MSIP_BASE) / 4;
+
+                // Solo el bit 0 de msip es escribible
+                if let Some(msip) = self.msip.get_mut(hart)
+                    && address.is_multiple_of(4)
+                {
+                    *msip = (val & 1) as u32;
+                }
+            }
+            MTIMECMP_BASE..MTIME => {
+                let hart = (address - MTIMECMP_BASE) / 8;
+
+                if let Some(mtimecmp) = self.mtimecmp.get_mut(hart) {
+                    *mtimecmp = write_byte_u64(*mtimecmp, address % 8, val);
+                }
+            }
+            MTIME..MTIME_END => self.mtime = write_byte_u64(self.mtime, address - MTIME, val),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.msip.fill(0);
+        self.mtimecmp.fill(u64::MAX);
+        self.mtime = 0;
+    }
+
+    // Avanza mtime un tick
+    fn tick(&mut self) {
+        self.mtime = self.mtime.wrapping_add(1);
+    }
+
+    fn save_state(&self, writer: &mut SnapshotWriter) {
+        for msip in &self.msip {
+            writer.write_u32(*msip);
+        }
+
+        for mtimecmp in &self.mtimecmp {
+            writer.write_u64(*mtimecmp);
+        }
+
+        writer.write_u64(self.mtime);
+    }
+
+    fn load_state(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        for msip in &mut self.msip {
+            *msip = reader.read_u32()?;
+        }
+
+        for mtimecmp in &mut self.mtimecmp {
+            *mtimecmp = reader.read_u64()?;
+        }
+
+        self.mtime = reader.read_u64()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+// HTIF (Host-Target Interface) de Spike. El programa escribe comandos de 64 bits
+// en tohost y el emulador responde en fromhost:
+//
+// 63      56 55      48 47                  0
+// | device  | command  |       payload       |
+//
+// Dispositivo 0 (syscall), comando 0: si el bit 0 del payload esta a 1 el
+// programa termina con codigo de salida payload >> 1. Los tests de riscv-tests
+// escriben 1 si han pasado y (test << 1) | 1 si ha fallado el test numero test.
+//
+// Dispositivo 1 (consola), comando 1: escribe el byte bajo del payload.
+//
+// En RV32 tohost se escribe con dos sw, primero la parte baja y despues la alta.
+// El comando se procesa al escribir el ultimo byte.
+
+use std::io::{Write, stdout};
+
+use crate::{
+    interpreter::snapshot::{SnapshotError, SnapshotReader, SnapshotWriter},
+    utils::write_byte_u64,
+};
+
+const DEVICE_SYSCALL: u8 = 0;
+const DEVICE_CONSOLE: u8 = 1;
+
+const CONSOLE_PUTCHAR: u8 = 1;
+
+const REG_SIZE: usize = 8;
+
+pub struct Htif {
+    to_host_address: usize,
+    from_host_address: usize,
+
+    to_host: u64,
+    from_host: u64,
+
+    exit_code: Option<u32>,
+}
+
+impl Htif {
+    pub fn new(to_host_address: usize, from_host_address: usize) -> Self {
+        Self {
+            to_host_address,
+            from_host_address,
+            to_host: 0,
+            from_host: 0,
+            exit_code: None,
+        }
+    }
+
+    pub fn contains(&self, address: usize) -> bool {
+        (self.to_host_address..self.to_host_address + REG_SIZE).contains(&address)
+            || (self.from_host_address..self.from_host_address + REG_SIZE).contains(&address)
+    }
+
+    // Borra los registros y el codigo de salida. Las direcciones se mantienen
+    pub fn reset(&mut self) {
+        self.to_host = 0;
+        self.from_host = 0;
+        self.exit_code = None;
+    }
+
+    // Codigo de salida si el programa ha terminado
+    pub fn get_exit_code(&self) -> Option<u32> {
+        self.exit_code
+    }
+
+    pub fn read(&self, address: usize) -> u8 {
+        if let Some(byte) = address.checked_sub(self.to_host_address)
+            && byte < REG_SIZE
+        {
+            return self.to_host.to_le_bytes()[byte];
+        }
+
+        self.from_host.to_le_bytes()[address - self.from_host_address]
+    }
+
+    pub fn write(&mut self, address: usize, val: u8) {
+        if let Some(byte) = address.checked_sub(self.to_host_address)
+            && byte < REG_SIZE
+        {
+            self.to_host = write_byte_u64(self.to_host, byte, val);
+
+            if byte == REG_SIZE - 1 && self.to_host != 0 {
+                self.handle_command();
+            }
+
+            return;
+        }
+
+        self.from_host = write_byte_u64(self.from_host, address - self.from_host_address, val);
+    }
+
+    fn handle_command(&mut self) {
+        let device = (self.to_host >> 56) as u8;
+        let command = (self.to_host >> 48) as u8;
+        let payload = self.to_host & 0xFFFF_FFFF_FFFF;
+
+        match (device, command) {
+            (DEVICE_SYSCALL, 0) if payload & 1 == 1 => self.exit_code = Some((payload >> 1) as u32),
+            (DEVICE_CONSOLE, CONSOLE_PUTCHAR) => {
+                let mut out = stdout();
+                let _ = out.write_all(&[payload as u8]);
+                let _ = out.flush();
+
+                self.from_host = ((device as u64) << 56) | ((command as u64) << 48);
+            }
+            _ => eprintln!("HTIF: comando no soportado {:#018X}", self.to_host),
+        }
+
+        // El programa espera a que tohost vuelva a 0 antes de mandar otro comando
+        self.to_host = 0;
+    }
+
+    pub fn save_state(&self, writer: &mut SnapshotWriter) {
+        writer.write_u32(self.to_host_address as u32);
+        writer.write_u32(self.from_host_address as u32);
+        writer.write_u64(self.to_host);
+        writer.write_u64(self.from_host);
+        writer.write_bool(self.exit_code.is_some());
+        writer.write_u32(self.exit_code.unwrap_or(0));
+    }
+
+    pub fn load_state(reader: &mut SnapshotReader) -> Result<Self, SnapshotError> {
+        let mut htif = Self::new(reader.read_u32()? as usize, reader.read_u32()? as usize);
+        htif.to_host = reader.read_u64()?;
+        htif.from_host = reader.read_u64()?;
+
+        let exited = reader.read_bool()?;
+        let exit_code = reader.read_u32()?;
+        htif.exit_code = exited.then_some(exit_code);
+
+        Ok(htif)
+    }
+}
@@ -1 +1,5 @@
+pub mod clint;
+pub mod htif;
+pub mod plic;
+pub mod rom;
 pub mod uart_16550;
@@ -0,0 +1,254 @@
+// PLIC compatible con el de SiFive. Cada hart tiene dos contextos: el 2 * hart
+// para el modo maquina y el 2 * hart + 1 para el modo supervisor.
+//
+// 0x000000 + 4 * fuente              prioridad
+// 0x001000                           bits de pendiente
+// 0x002000 + 0x80 * contexto         bits de habilitacion
+// 0x200000 + 0x1000 * contexto       umbral
+// 0x200004 + 0x1000 * contexto       claim/complete
+//
+// Solo admite accesos de 32 bits.
+
+use crate::interpreter::{
+    device::{BusError, Device},
+    snapshot::{SnapshotError, SnapshotReader, SnapshotWriter},
+};
+
+const NUM_SOURCES: usize = 1024;
+const NUM_WORDS: usize = NUM_SOURCES / 32;
+
+const PRIORITY_BASE: usize = 0x000000;
+const PENDING_BASE: usize = 0x001000;
+const PENDING_END: usize = PENDING_BASE + NUM_WORDS * 4;
+const ENABLE_BASE: usize = 0x002000;
+const ENABLE_STRIDE: usize = 0x80;
+const CONTEXT_BASE: usize = 0x200000;
+const CONTEXT_STRIDE: usize = 0x1000;
+
+const CONTEXT_THRESHOLD: usize = 0x0;
+const CONTEXT_CLAIM: usize = 0x4;
+
+// Prioridades de 3 bits (0-7)
+const PRIORITY_MASK: u32 = 0x7;
+
+pub struct Plic {
+    priorities: Vec<u32>,
+    pending: [u32; NUM_WORDS],
+    // Interrupciones reclamadas que aun no se han completado
+    claimed: [u32; NUM_WORDS],
+
+    enables: Vec<[u32; NUM_WORDS]>,
+    thresholds: Vec<u32>,
+}
+
+impl Plic {
+    pub fn new(num_harts: usize) -> Self {
+        let num_contexts = num_harts * 2;
+
+        Self {
+            priorities: vec![0; NUM_SOURCES],
+            pending: [0; NUM_WORDS],
+            claimed: [0; NUM_WORDS],
+            enables: vec![[0; NUM_WORDS]; num_contexts],
+            thresholds: vec![0; num_contexts],
+        }
+    }
+
+    pub fn machine_context(hart_id: usize) -> usize {
+        hart_id * 2
+    }
+
+    pub fn supervisor_context(hart_id: usize) -> usize {
+        hart_id * 2 + 1
+    }
+
+    // La fuente 0 no existe
+    pub fn is_valid_source(source: u32) -> bool {
+        (1..NUM_SOURCES as u32).contains(&source)
+    }
+
+    // Lo llama el dispositivo cuando activa su linea de interrupcion. Si la
+    // fuente esta reclamada se queda pendiente y no se entrega hasta que se
+    // complete, igual que hace el gateway del PLIC
+    pub fn raise(&mut self, source: u32) {
+        if !Self::is_valid_source(source) {
+            return;
+        }
+
+        set_bit(&mut self.pending, source as usize, true);
+    }
+
+    // Fuente pendiente y habilitada de mayor prioridad que supera el umbral
+    fn get_best_source(&self, context: usize) -> Option<usize> {
+        let mut best: Option<(usize, u32)> = None;
+
+        // Se llama antes de cada instruccion, asi que se recorren solo los bits
+        // pendientes, habilitados y sin reclamar de cada palabra
+        let words = self.pending.iter().zip(&self.claimed).zip(&self.enables[context]);
+
+        for (word, ((pending, claimed), enables)) in words.enumerate() {
+            let mut candidates = pending & !claimed & enables;
+
+            while candidates != 0 {
+                let source = word * 32 + candidates.trailing_zeros() as usize;
+                candidates &= candidates - 1;
+
+                // La fuente 0 no existe
+                if source == 0 {
+                    continue;
+                }
+
+                let priority = self.priorities[source];
+
+                // En caso de empate gana el identificador mas bajo
+                if priority > self.thresholds[context] && best.is_none_or(|(_, best_priority)| priority > best_priority) {
+                    best = Some((source, priority));
+                }
+            }
+        }
+
+        best.map(|(source, _)| source)
+    }
+
+    pub fn has_interrupt(&self, context: usize) -> bool {
+        self.get_best_source(context).is_some()
+    }
+
+    fn claim(&mut self, context: usize) -> u32 {
+        match self.get_best_source(context) {
+            Some(source) => {
+                set_bit(&mut self.pending, source, false);
+                set_bit(&mut self.claimed, source, true);
+
+                source as u32
+            }
+            None => 0,
+        }
+    }
+
+    fn complete(&mut self, context: usize, source: u32) {
+        let source = source as usize;
+
+        // Se ignoran los identificadores que no estan habilitados en el contexto
+        if source < NUM_SOURCES && is_set(&self.enables[context], source) {
+            set_bit(&mut self.claimed, source, false);
+        }
+    }
+
+    fn get_context(&self, address: usize, base: usize, stride: usize) -> Option<(usize, usize)> {
+        let context = (address - base) / stride;
+        let offset = (address - base) % stride;
+
+        (context < self.thresholds.len()).then_some((context, offset))
+    }
+}
+
+impl Device for Plic {
+    // Solo admite accesos de 32 bits
+    fn read_u8(&mut self, _offset: u64) -> Result<u8, BusError> {
+        Err(BusError)
+    }
+
+    fn write_u8(&mut self, _offset: u64, _val: u8) -> Result<(), BusError> {
+        Err(BusError)
+    }
+
+    fn read_u16(&mut self, _offset: u64) -> Result<u16, BusError> {
+        Err(BusError)
+    }
+
+    fn write_u16(&mut self, _offset: u64, _val: u16) -> Result<(), BusError> {
+        Err(BusError)
+    }
+
+    fn read_u32(&mut self, offset: u64) -> Result<u32, BusError> {
+        let address = offset as usize;
+
+        let val = match address {
+            PRIORITY_BASE..PENDING_BASE => self.priorities[address / 4],
+            PENDING_BASE..PENDING_END => self.pending[(address - PENDING_BASE) / 4],
+            ENABLE_BASE..CONTEXT_BASE => match self.get_context(address, ENABLE_BASE, ENABLE_STRIDE) {
+                Some((context, offset)) if offset < NUM_WORDS * 4 => self.enables[context][offset / 4],
+                _ => 0,
+            },
+            CONTEXT_BASE.. => match self.get_context(address, CONTEXT_BASE, CONTEXT_STRIDE) {
+                Some((context, CONTEXT_THRESHOLD)) => self.thresholds[context],
+                Some((context, CONTEXT_CLAIM)) => self.claim(context),
+                _ => 0,
+            },
+            _ => 0,
+        };
+
+        Ok(val)
+    }
+
+    fn write_u32(&mut self, offset: u64, val: u32) -> Result<(), BusError> {
+        let address = offset as usize;
+
+        match address {
+            // La fuente 0 no existe
+            PRIORITY_BASE..PENDING_BASE if address >= 4 => self.priorities[address / 4] = val & PRIORITY_MASK,
+            ENABLE_BASE..CONTEXT_BASE => {
+                if let Some((context, offset)) = self.get_context(address, ENABLE_BASE, ENABLE_STRIDE)
+                    && offset < NUM_WORDS * 4
+                {
+                    let mut val = val;
+
+                    if offset == 0 {
+                        val &= !1;
+                    }
+
+                    self.enables[context][offset / 4] = val;
+                }
+            }
+            CONTEXT_BASE.. => match self.get_context(address, CONTEXT_BASE, CONTEXT_STRIDE) {
+                Some((context, CONTEXT_THRESHOLD)) => self.thresholds[context] = val & PRIORITY_MASK,
+                Some((context, CONTEXT_CLAIM)) => self.complete(context, val),
+                _ => {}
+            },
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.priorities.fill(0);
+        self.pending.fill(0);
+        self.claimed.fill(0);
+        self.enables.fill([0; NUM_WORDS]);
+        self.thresholds.fill(0);
+    }
+
+    fn save_state(&self, writer: &mut SnapshotWriter) {
+        let words = self.priorities.iter().chain(&self.pending).chain(&self.claimed);
+        let contexts = self.enables.iter().flatten().chain(&self.thresholds);
+
+        for word in words.chain(contexts) {
+            writer.write_u32(*word);
+        }
+    }
+
+    fn load_state(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        let words = self.priorities.iter_mut().chain(&mut self.pending).chain(&mut self.claimed);
+        let contexts = self.enables.iter_mut().flatten().chain(&mut self.thresholds);
+
+        for word in words.chain(contexts) {
+            *word = reader.read_u32()?;
+        }
+
+        Ok(())
+    }
+}
+
+fn is_set(bits: &[u32; NUM_WORDS], source: usize) -> bool {
+    bits[source / 32] & (1 << (source % 32)) != 0
+}
+
+fn set_bit(bits: &mut [u32; NUM_WORDS], source: usize, val: bool) {
+    if val {
+        bits[source / 32] |= 1 << (source % 32);
+    } else {
+        bits[source / 32] &= !(1 << (source % 32));
+    }
+}
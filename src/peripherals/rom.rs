@@ -0,0 +1,37 @@
+// Memoria de solo lectura. Las escrituras provocan un fallo de acceso
+
+use crate::interpreter::{
+    device::{BusError, Device},
+    snapshot::{SnapshotError, SnapshotReader, SnapshotWriter},
+};
+
+pub struct Rom {
+    data: Vec<u8>,
+}
+
+impl Rom {
+    pub fn new(size: usize) -> Self {
+        Self { data: vec![0; size] }
+    }
+}
+
+impl Device for Rom {
+    fn read_u8(&mut self, offset: u64) -> Result<u8, BusError> {
+        self.data.get(offset as usize).copied().ok_or(BusError)
+    }
+
+    fn write_u8(&mut self, _offset: u64, _val: u8) -> Result<(), BusError> {
+        Err(BusError)
+    }
+
+    fn save_state(&self, writer: &mut SnapshotWriter) {
+        writer.write_bytes(&self.data);
+    }
+
+    fn load_state(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        let len = self.data.len();
+        self.data.copy_from_slice(reader.read_bytes(len)?);
+
+        Ok(())
+    }
+}
@@ -10,6 +10,11 @@ use std::{
     thread,
 };
 
+use crate::interpreter::{
+    device::{BusError, Device},
+    snapshot::{SnapshotError, SnapshotReader, SnapshotWriter},
+};
+
 const UART_RHR_THR: usize = 0; 
 const UART_LSR: usize = 5;
 
@@ -57,26 +62,27 @@ impl Uart16550 {
 
         Self { regs, interrupt }
     }
+}
 
-    pub fn has_interrupt(&self) -> bool {
-        self.interrupt.swap(false, Ordering::Acquire)
-    }
-
-    pub fn read(&self, address: usize) -> u8 {
+impl Device for Uart16550 {
+    fn read_u8(&mut self, offset: u64) -> Result<u8, BusError> {
+        let address = offset as usize;
         let (regs, cvar) = &*self.regs;
         let mut regs = regs.lock().expect("Mutex envenendado");
-        match address {
+        let val = match address {
             UART_RHR_THR => {
                 cvar.notify_one();
                 regs[UART_LSR] &= !UART_LSR_RX;
                 regs[UART_RHR_THR]
             }
             _ => regs[address]
-        }
+        };
+
+        Ok(val)
     }
 
-    pub fn write(&mut self, address: usize, val: u8) {
-        println!("{:08X} {:08X}", address, val);
+    fn write_u8(&mut self, offset: u64, val: u8) -> Result<(), BusError> {
+        let address = offset as usize;
 
         let (regs, _cvar) = &*self.regs;
         let mut regs = regs.lock().expect("Mutex envenendado");
@@ -87,5 +93,44 @@ impl Uart16550 {
             },
             _ => regs[address] = val
         }
+
+        Ok(())
+    }
+
+    // Se descartan el dato recibido y la interrupcion pendiente
+    fn reset(&mut self) {
+        let (regs, cvar) = &*self.regs;
+        let mut regs = regs.lock().expect("Mutex envenendado");
+
+        regs.fill(0);
+        regs[UART_LSR] |= UART_LSR_TX;
+        self.interrupt.store(false, Ordering::Release);
+        cvar.notify_one();
+    }
+
+    fn take_interrupt(&mut self) -> bool {
+        self.interrupt.swap(false, Ordering::Acquire)
+    }
+
+    fn save_state(&self, writer: &mut SnapshotWriter) {
+        let (regs, _cvar) = &*self.regs;
+        let regs = regs.lock().expect("Mutex envenendado");
+
+        writer.write_bytes(&*regs);
+        writer.write_bool(self.interrupt.load(Ordering::Acquire));
+    }
+
+    // El hilo de lectura de stdin puede estar esperando a que se lea el dato
+    // anterior, asi que se le avisa por si la instantanea tiene RHR vacio
+    fn load_state(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        let (regs, cvar) = &*self.regs;
+        let mut regs = regs.lock().expect("Mutex envenendado");
+
+        let len = regs.len();
+        regs.copy_from_slice(reader.read_bytes(len)?);
+        self.interrupt.store(reader.read_bool()?, Ordering::Release);
+        cvar.notify_one();
+
+        Ok(())
     }
 }
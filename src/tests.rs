@@ -1,3128 +1,1621 @@
 
-use crate::interpreter::Interpreter;
+use crate::interpreter::{ExitStatus, Interpreter, MisalignedAccess};
+use crate::interpreter::device::{BusError, Device};
 use ntest::timeout;
 
+// Dispositivo que activa su linea de interrupcion cada vez que se escribe en el
+#[derive(Default)]
+struct IrqTrigger {
+    raised: bool,
+}
 
-#[test]
-#[timeout(2000)]
-fn rv32ui_v_sw() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-sw.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
+impl Device for IrqTrigger {
+    fn read_u8(&mut self, _offset: u64) -> Result<u8, BusError> {
+        Ok(0)
+    }
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+    fn write_u8(&mut self, _offset: u64, _val: u8) -> Result<(), BusError> {
+        self.raised = true;
+        Ok(())
+    }
 
-    assert!(msg.contains("PASS"));
+    fn take_interrupt(&mut self) -> bool {
+        std::mem::take(&mut self.raised)
+    }
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_and() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-and.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32mi_p_breakpoint() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-breakpoint.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32mi_p_pmpaddr() {
-    use std::panic;
+fn rv32mi_p_clint() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-clint.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32mi-p-pmpaddr.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32mi_p_sbreak() {
-    use std::panic;
+fn rv32mi_p_counters() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-counters.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32mi-p-sbreak.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_sra() {
-    use std::panic;
+fn rv32mi_p_csr() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-csr.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-sra.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32mi_p_fence_i_smp() {
+    let mut interpreter = Interpreter::with_harts(2);
+    interpreter.set_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-fence_i_smp.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_or() {
-    use std::panic;
+fn rv32mi_p_hpm() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-hpm.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-or.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32mi_p_illegal() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-illegal.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32si_p_sbreak() {
-    use std::panic;
+fn rv32mi_p_instret_overflow() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-instret_overflow.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32si-p-sbreak.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32mi_p_lh_misaligned() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-lh-misaligned.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ua_v_amomaxu_w() {
-    use std::panic;
+fn rv32mi_p_lw_misaligned() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-lw-misaligned.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-v-amomaxu_w.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32mi_p_ma_addr() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-ma_addr.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_lbu() {
-    use std::panic;
+fn rv32mi_p_ma_fetch() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-ma_fetch.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-lbu.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32mi_p_ma_trap() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.set_misaligned_access(MisalignedAccess::Trap);
+    interpreter.load_hex("rv_tests/rv32mi-p-ma_trap.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32mi_p_breakpoint() {
-    use std::panic;
+fn rv32mi_p_mcsr() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-mcsr.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32mi-p-breakpoint.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32mi_p_plic() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.add_device(0x10001000, 0x1000, Some(11), Box::new(IrqTrigger::default())).unwrap();
+    interpreter.load_hex("rv_tests/rv32mi-p-plic.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ua_p_amominu_w() {
-    use std::panic;
+fn rv32mi_p_pmp() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-pmp.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-p-amominu_w.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32mi_p_pmp_fetch() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-pmp_fetch.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_bgeu() {
-    use std::panic;
+fn rv32mi_p_pmpaddr() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-pmpaddr.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-bgeu.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32mi_p_sbreak() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-sbreak.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_addi() {
-    use std::panic;
+fn rv32mi_p_scall() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-scall.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-addi.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32mi_p_sh_misaligned() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-sh-misaligned.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32um_v_mulh() {
-    use std::panic;
+fn rv32mi_p_shamt() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-shamt.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32um-v-mulh.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32mi_p_smp() {
+    let mut interpreter = Interpreter::with_harts(2);
+    interpreter.set_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-smp.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_slli() {
-    use std::panic;
+fn rv32mi_p_sw_misaligned() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-sw-misaligned.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-slli.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32mi_p_tvec() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-tvec.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_auipc() {
-    use std::panic;
+fn rv32mi_p_wfi() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-wfi.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-auipc.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32mi_p_zicntr() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32mi-p-zicntr.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ua_v_amoswap_w() {
-    use std::panic;
+fn rv32si_p_csr() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32si-p-csr.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-v-amoswap_w.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32si_p_dirty() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32si-p-dirty.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_slt() {
-    use std::panic;
+fn rv32si_p_ma_cross() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32si-p-ma_cross.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-slt.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32si_p_ma_fetch() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32si-p-ma_fetch.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_auipc() {
-    use std::panic;
+fn rv32si_p_sbreak() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32si-p-sbreak.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-auipc.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32si_p_scall() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32si-p-scall.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32um_v_div() {
-    use std::panic;
+fn rv32si_p_tlb() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32si-p-tlb.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32um-v-div.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32si_p_wfi() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32si-p-wfi.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
 fn rv32ua_p_amoadd_w() {
-    use std::panic;
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-p-amoadd_w.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-p-amoadd_w.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_xori() {
-    use std::panic;
+fn rv32ua_p_amoand_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-p-amoand_w.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-xori.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ua_p_amomax_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-p-amomax_w.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_srli() {
-    use std::panic;
+fn rv32ua_p_amomaxu_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-p-amomaxu_w.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-srli.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ua_p_amomin_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-p-amomin_w.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32mi_p_zicntr() {
-    use std::panic;
+fn rv32ua_p_amominu_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-p-amominu_w.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32mi-p-zicntr.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ua_p_amoor_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-p-amoor_w.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_bne() {
-    use std::panic;
+fn rv32ua_p_amoswap_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-p-amoswap_w.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-bne.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ua_p_amoxor_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-p-amoxor_w.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_ma_data() {
-    use std::panic;
+fn rv32ua_p_lrsc() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-p-lrsc.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-ma_data.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ua_v_amoadd_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-v-amoadd_w.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32mi_p_ma_fetch() {
-    use std::panic;
+fn rv32ua_v_amoand_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-v-amoand_w.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32mi-p-ma_fetch.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ua_v_amomax_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-v-amomax_w.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_lhu() {
-    use std::panic;
+fn rv32ua_v_amomaxu_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-v-amomaxu_w.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-lhu.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ua_v_amomin_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-v-amomin_w.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_lh() {
-    use std::panic;
+fn rv32ua_v_amominu_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-v-amominu_w.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-lh.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ua_v_amoor_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-v-amoor_w.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ua_v_amoand_w() {
-    use std::panic;
+fn rv32ua_v_amoswap_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-v-amoswap_w.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-v-amoand_w.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ua_v_amoxor_w() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-v-amoxor_w.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_slti() {
-    use std::panic;
+fn rv32ua_v_lrsc() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ua-v-lrsc.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-slti.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32uc_p_rvc_extra() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32uc-p-rvc_extra.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_blt() {
-    use std::panic;
+fn rv32ud_p_d_extra() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.set_misaligned_access(MisalignedAccess::Trap);
+    interpreter.load_hex("rv_tests/rv32ud-p-d_extra.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-blt.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32uf_p_f_extra() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32uf-p-f_extra.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_sltiu() {
-    use std::panic;
+fn rv32ui_p_add() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-add.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-sltiu.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_addi() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-addi.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_sh() {
-    use std::panic;
+fn rv32ui_p_and() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-and.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-sh.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_andi() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-andi.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_add() {
-    use std::panic;
+fn rv32ui_p_auipc() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-auipc.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-add.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_beq() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-beq.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_add() {
-    use std::panic;
+fn rv32ui_p_bge() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-bge.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-add.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_bgeu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-bgeu.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32um_v_mul() {
-    use std::panic;
+fn rv32ui_p_blt() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-blt.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32um-v-mul.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_bltu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-bltu.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ua_p_amoor_w() {
-    use std::panic;
+fn rv32ui_p_bne() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-bne.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-p-amoor_w.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_elf() {
+    let mut interpreter = Interpreter::default();
+    interpreter.load_elf("rv_tests/rv32ui-p-elf.elf").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
 fn rv32ui_p_fence_i() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-fence_i.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-fence_i.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_fence_i() {
-    use std::panic;
+fn rv32ui_p_jal() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-jal.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-fence_i.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_jalr() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-jalr.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ua_v_amomax_w() {
-    use std::panic;
+fn rv32ui_p_lb() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-lb.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-v-amomax_w.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_lbu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-lbu.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_sub() {
-    use std::panic;
+fn rv32ui_p_ld_st() {
+    let mut interpreter = Interpreter::default();
+    interpreter.load_elf("rv_tests/rv32ui-p-ld_st.elf").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-sub.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_lh() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-lh.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32um_p_mulhsu() {
-    use std::panic;
+fn rv32ui_p_lhu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-lhu.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32um-p-mulhsu.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_lui() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-lui.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_lh() {
-    use std::panic;
+fn rv32ui_p_lw() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-lw.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-lh.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_ma_data() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-ma_data.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_lui() {
-    use std::panic;
+fn rv32ui_p_or() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-or.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-lui.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_ori() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-ori.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_slti() {
-    use std::panic;
+fn rv32ui_p_sb() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-sb.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-slti.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_sh() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-sh.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32mi_p_lh_misaligned() {
-    use std::panic;
+fn rv32ui_p_simple() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-simple.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32mi-p-lh-misaligned.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_sll() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-sll.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_ld_st() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80002000);
-        interpreter.load_hex("rv_tests/rv32ui-p-ld_st.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32um_v_remu() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32um-v-remu.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32um_p_divu() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32um-p-divu.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32um_v_rem() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32um-v-rem.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32mi_p_scall() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32mi-p-scall.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_v_st_ld() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-st_ld.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ua_v_amoadd_w() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-v-amoadd_w.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_p_beq() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-beq.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_v_srai() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-srai.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_v_simple() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-simple.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32um_v_mulhsu() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32um-v-mulhsu.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32mi_p_csr() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32mi-p-csr.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32mi_p_instret_overflow() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32mi-p-instret_overflow.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ua_p_amomin_w() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-p-amomin_w.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32si_p_ma_fetch() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32si-p-ma_fetch.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ua_v_amoxor_w() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-v-amoxor_w.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_p_lb() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-lb.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_v_jalr() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-jalr.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ua_p_amoxor_w() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-p-amoxor_w.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ua_v_lrsc() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-v-lrsc.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32um_p_div() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32um-p-div.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_v_xor() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-xor.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ua_p_amoswap_w() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-p-amoswap_w.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32um_v_divu() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32um-v-divu.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_v_ld_st() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-ld_st.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_p_or() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-or.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_v_bge() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-bge.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32si_p_csr() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32si-p-csr.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_v_lb() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-lb.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_v_bne() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-bne.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_v_and() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-and.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32um_p_remu() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32um-p-remu.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_p_jal() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-jal.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_p_sw() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-sw.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32um_p_mulhu() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32um-p-mulhu.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_v_beq() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-beq.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_v_lw() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-lw.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ua_p_amoand_w() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-p-amoand_w.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_p_sltu() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-sltu.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_p_jalr() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-jalr.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_v_sltiu() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-sltiu.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_v_xori() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-xori.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_v_sb() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-sb.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_p_ori() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-ori.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32si_p_wfi() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32si-p-wfi.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ua_v_amominu_w() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-v-amominu_w.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_p_andi() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-andi.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
-}
-
-
-#[test]
-#[timeout(2000)]
-fn rv32ui_p_srai() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-srai.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_p_slli() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-slli.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_jal() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-jal.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_p_slt() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-slt.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_lui() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-lui.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_p_slti() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-slti.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_ori() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-ori.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_p_sltiu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-sltiu.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_slli() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-slli.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_p_sltu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-sltu.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_sra() {
-    use std::panic;
+fn rv32ui_p_sra() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-sra.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-sra.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_srai() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-srai.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32um_v_mulhu() {
-    use std::panic;
+fn rv32ui_p_srl() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-srl.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32um-v-mulhu.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_srli() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-srli.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ua_p_amomaxu_w() {
-    use std::panic;
+fn rv32ui_p_st_ld() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-st_ld.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-p-amomaxu_w.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_sub() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-sub.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_srli() {
-    use std::panic;
+fn rv32ui_p_sw() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-sw.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-srli.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_p_xor() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-xor.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32mi_p_lw_misaligned() {
-    use std::panic;
+fn rv32ui_p_xori() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-p-xori.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32mi-p-lw-misaligned.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_v_add() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-add.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
 fn rv32ui_v_addi() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-addi.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-addi.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_sub() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-sub.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_v_and() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-and.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_sll() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-sll.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_v_andi() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-andi.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ua_p_lrsc() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-p-lrsc.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_v_auipc() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-auipc.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32mi_p_illegal() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32mi-p-illegal.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_v_beq() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-beq.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_sltu() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-sltu.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_v_bge() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-bge.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32si_p_dirty() {
-    use std::panic;
+fn rv32ui_v_bgeu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-bgeu.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32si-p-dirty.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_v_blt() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-blt.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
 fn rv32ui_v_bltu() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-bltu.hex");
-        interpreter.run();
-    });
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-bltu.hex").unwrap();
 
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
-
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_bltu() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-bltu.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_v_bne() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-bne.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ua_p_amomax_w() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-p-amomax_w.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_v_fence_i() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-fence_i.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ua_v_amomin_w() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-v-amomin_w.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_v_jal() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-jal.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32um_p_mul() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32um-p-mul.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_v_jalr() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-jalr.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32si_p_scall() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32si-p-scall.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_v_lb() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-lb.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_bgeu() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-bgeu.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_v_lbu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-lbu.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_slt() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-slt.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_v_ld_st() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-ld_st.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_lw() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-lw.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_v_lh() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-lh.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32mi_p_shamt() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32mi-p-shamt.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_v_lhu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-lhu.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32mi_p_ma_addr() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32mi-p-ma_addr.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_v_lui() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-lui.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_st_ld() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-st_ld.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+fn rv32ui_v_lw() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-lw.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_lbu() {
-    use std::panic;
+fn rv32ui_v_ma_data() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-ma_data.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-lbu.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_v_or() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-or.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_lhu() {
-    use std::panic;
+fn rv32ui_v_ori() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-ori.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-lhu.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_v_sb() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-sb.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_ma_data() {
-    use std::panic;
+fn rv32ui_v_sh() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-sh.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-ma_data.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_v_simple() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-simple.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_sb() {
-    use std::panic;
+fn rv32ui_v_sll() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-sll.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-sb.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_v_slli() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-slli.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_simple() {
-    use std::panic;
+fn rv32ui_v_slt() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-slt.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-simple.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_v_slti() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-slti.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_bge() {
-    use std::panic;
+fn rv32ui_v_sltiu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-sltiu.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-bge.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_v_sltu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-sltu.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32mi_p_sh_misaligned() {
-    use std::panic;
+fn rv32ui_v_sra() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-sra.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32mi-p-sh-misaligned.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_v_srai() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-srai.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32um_p_rem() {
-    use std::panic;
+fn rv32ui_v_srl() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-srl.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32um-p-rem.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_v_srli() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-srli.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_andi() {
-    use std::panic;
+fn rv32ui_v_st_ld() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-st_ld.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-andi.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_v_sub() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-sub.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_srl() {
-    use std::panic;
+fn rv32ui_v_sw() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-sw.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-srl.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32ui_v_xor() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-xor.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ua_v_amoor_w() {
-    use std::panic;
+fn rv32ui_v_xori() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32ui-v-xori.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ua-v-amoor_w.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32um_p_div() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32um-p-div.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_sh() {
-    use std::panic;
+fn rv32um_p_divu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32um-p-divu.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-sh.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32um_p_mul() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32um-p-mul.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
 fn rv32um_p_mulh() {
-    use std::panic;
-
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32um-p-mulh.hex");
-        interpreter.run();
-    });
-
-    let err = result.expect_err("Expected panic");
-
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32um-p-mulh.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_blt() {
-    use std::panic;
+fn rv32um_p_mulhsu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32um-p-mulhsu.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-blt.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32um_p_mulhu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32um-p-mulhu.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32mi_p_sw_misaligned() {
-    use std::panic;
+fn rv32um_p_rem() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32um-p-rem.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32mi-p-sw-misaligned.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32um_p_remu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32um-p-remu.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_srl() {
-    use std::panic;
+fn rv32um_v_div() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32um-v-div.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-srl.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32um_v_divu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32um-v-divu.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_p_xor() {
-    use std::panic;
+fn rv32um_v_mul() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32um-v-mul.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-p-xor.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32um_v_mulh() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32um-v-mulh.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32mi_p_mcsr() {
-    use std::panic;
+fn rv32um_v_mulhsu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32um-v-mulhsu.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32mi-p-mcsr.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32um_v_mulhu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32um-v-mulhu.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
 
 #[test]
 #[timeout(2000)]
-fn rv32ui_v_sll() {
-    use std::panic;
+fn rv32um_v_rem() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32um-v-rem.hex").unwrap();
 
-    let result = panic::catch_unwind(|| {
-        let mut interpreter = Interpreter::new_test(0x80001000);
-        interpreter.load_hex("rv_tests/rv32ui-v-sll.hex");
-        interpreter.run();
-    });
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
+}
 
-    let err = result.expect_err("Expected panic");
 
-    let msg = err.downcast_ref::<&str>()
-        .map(|s| *s)
-        .or_else(|| err.downcast_ref::<String>().map(|s| s.as_str()))
-        .unwrap();
+#[test]
+#[timeout(2000)]
+fn rv32um_v_remu() {
+    let mut interpreter = Interpreter::with_htif(0x80001000, 0x80001040);
+    interpreter.load_hex("rv_tests/rv32um-v-remu.hex").unwrap();
 
-    assert!(msg.contains("PASS"));
+    assert_eq!(interpreter.run(), ExitStatus::Halted { exit_code: 0 });
 }
 
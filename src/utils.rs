@@ -0,0 +1,17 @@
+// Funciones auxiliares que se usan desde varios modulos
+
+// Acepta numeros en hexadecimal (0x80000000) o en decimal
+pub fn parse_number(text: &str) -> Option<u32> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+// Sustituye el byte numero byte (en little endian) de un registro de 64 bits
+pub(crate) fn write_byte_u64(reg: u64, byte: usize, val: u8) -> u64 {
+    let mut bytes = reg.to_le_bytes();
+    bytes[byte] = val;
+
+    u64::from_le_bytes(bytes)
+}